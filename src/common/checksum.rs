@@ -0,0 +1,49 @@
+use md5::Md5;
+use sha2::{Digest, Sha256};
+
+/// Algorithm of a checksum computed while data is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumAlgorithm {
+    Sha256,
+    /// Matches the S3 ETag of objects uploaded in a single request.
+    Md5,
+}
+
+/// Incrementally computes a checksum over chunks of data.
+pub(crate) enum ChecksumHasher {
+    Sha256(Sha256),
+    Md5(Md5),
+}
+
+impl ChecksumHasher {
+    pub(crate) fn new(algorithm: ChecksumAlgorithm) -> Self {
+        match algorithm {
+            ChecksumAlgorithm::Sha256 => ChecksumHasher::Sha256(Sha256::new()),
+            ChecksumAlgorithm::Md5 => ChecksumHasher::Md5(Md5::new()),
+        }
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        match self {
+            ChecksumHasher::Sha256(hasher) => hasher.update(data),
+            ChecksumHasher::Md5(hasher) => hasher.update(data),
+        }
+    }
+
+    /// Returns the raw digest.
+    pub(crate) fn finalize(self) -> Vec<u8> {
+        match self {
+            ChecksumHasher::Sha256(hasher) => hasher.finalize().to_vec(),
+            ChecksumHasher::Md5(hasher) => hasher.finalize().to_vec(),
+        }
+    }
+
+    /// Returns the digest as lowercase hex.
+    pub(crate) fn finalize_hex(self) -> String {
+        Self::to_hex(&self.finalize())
+    }
+
+    pub(crate) fn to_hex(digest: &[u8]) -> String {
+        digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+}
@@ -1,3 +1,8 @@
 #[allow(dead_code)]
 pub mod utils;
-pub mod stream;
\ No newline at end of file
+pub mod stream;
+#[cfg(any(feature = "file", feature = "s3"))]
+pub mod checksum;
+
+#[cfg(test)]
+mod test;
\ No newline at end of file
@@ -4,9 +4,11 @@ use anyhow::Error;
 use bytes::{Bytes, BytesMut};
 use futures::{Stream, StreamExt};
 
-pub struct ByteStream(
-    Pin<Box<dyn Stream<Item = Result<Bytes, Error>> + Send + Sync>>
-);
+pub struct ByteStream {
+    stream: Pin<Box<dyn Stream<Item = Result<Bytes, Error>> + Send + Sync>>,
+    content_length: Option<u64>,
+    content_type: Option<String>,
+}
 
 impl ByteStream {
     pub fn new<S, E>(stream: S) -> Self
@@ -15,15 +17,41 @@ impl ByteStream {
         E: Into<Error> + 'static,
     {
         let stream = stream.map(|res| res.map_err(Into::into));
-        Self(Box::pin(stream))
+        Self {
+            stream: Box::pin(stream),
+            content_length: None,
+            content_type: None,
+        }
+    }
+
+    /// Sets the total length of the stream in bytes, if known by the source.
+    pub fn with_content_length(mut self, content_length: u64) -> Self {
+        self.content_length = Some(content_length);
+        self
+    }
+
+    /// Sets the content-type of the stream, if known by the source.
+    pub fn with_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    /// Returns the total length of the stream in bytes, if known.
+    pub fn content_length(&self) -> Option<u64> {
+        self.content_length
+    }
+
+    /// Returns the content-type of the stream, if known.
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
     }
 
     pub fn inner_stream(self) -> Pin<Box<dyn Stream<Item = Result<Bytes, Error>> + Send + Sync>> {
-        self.0
+        self.stream
     }
 
     pub async fn to_bytes(self) -> anyhow::Result<Bytes> {
-        let mut stream = self.0;
+        let mut stream = self.stream;
         let mut buffer = BytesMut::new();
 
         while let Some(chunk) = stream.next().await {
@@ -35,7 +63,7 @@ impl ByteStream {
     }
 
     pub async fn next(&mut self) -> Option<anyhow::Result<Bytes>> {
-        match self.0.next().await {
+        match self.stream.next().await {
             Some(Ok(bytes)) => Some(Ok(bytes)),
             Some(Err(e)) => Some(Err(e)),
             None => None,
@@ -45,11 +73,12 @@ impl ByteStream {
 
 impl From<Bytes> for ByteStream {
     fn from(bytes: Bytes) -> Self {
+        let content_length = bytes.len() as u64;
         let stream = futures::stream::once(async move { 
             Ok::<Bytes, Error>(bytes) 
         });
 
-        Self(Box::pin(stream))
+        Self::new(stream).with_content_length(content_length)
     }
 }
 
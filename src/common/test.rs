@@ -0,0 +1,16 @@
+use std::time::Duration;
+
+use crate::common::utils;
+
+#[test]
+fn retry_delay() {
+    let backoff = Duration::from_millis(100);
+    let max = Duration::from_secs(10);
+
+    let delay = utils::retry_delay(backoff, 1, max);
+    assert!(delay >= backoff && delay <= backoff.mul_f64(1.5));
+
+    for attempt in [7, 8, 20, 64, u32::MAX] {
+        assert!(utils::retry_delay(backoff, attempt, max) <= max);
+    }
+}
@@ -1,3 +1,5 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
 pub fn parse_host(host: &str, default_port: u16) -> anyhow::Result<(&str, u16)> {
     if let Some((host, port)) = host.split_once(":") {
         let port: u16 = port.parse()?;
@@ -5,4 +7,11 @@ pub fn parse_host(host: &str, default_port: u16) -> anyhow::Result<(&str, u16)>
     } else {
         Ok((host, default_port))
     }
-}
\ No newline at end of file
+}
+
+/// Returns the exponential backoff for the attempt, starting at `backoff` with up to 50% jitter added and capped at `max`.
+pub fn retry_delay(backoff: Duration, attempt: u32, max: Duration) -> Duration {
+    let delay = backoff.saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)));
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().subsec_nanos();
+    delay.saturating_add(delay.mul_f64(nanos as f64 / 2_000_000_000.0)).min(max)
+}
@@ -1,19 +1,29 @@
-use std::{marker::PhantomData, path::{Path, PathBuf}};
+use std::{hash::{BuildHasher, Hasher, RandomState}, marker::PhantomData, path::{Path, PathBuf}};
 
 use bytes::Bytes;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::{fs::OpenOptions, io::{AsyncReadExt, AsyncWriteExt}};
 use tokio_util::io::ReaderStream;
 
-use crate::common::stream::ByteStream;
+use crate::common::{checksum::{ChecksumAlgorithm, ChecksumHasher}, stream::ByteStream};
 
 pub struct Empty;
 pub struct Write;
+pub struct Append;
 pub struct Read;
 pub struct Copy;
 pub struct Move;
 
+#[cfg(windows)]
+const NEWLINE: &[u8] = b"\r\n";
+#[cfg(not(windows))]
+const NEWLINE: &[u8] = b"\n";
+
 pub struct FileClient<State> {
     path: Option<PathBuf>,
+    separator: Option<Bytes>,
+    header_line: Option<Bytes>,
+    atomic: bool,
+    create_dirs: bool,
     _state: PhantomData<State>,
 }
 
@@ -21,34 +31,42 @@ impl FileClient<Empty> {
     pub fn new() -> Self {
         FileClient  {
             path: None,
+            separator: Some(Bytes::from_static(NEWLINE)),
+            header_line: None,
+            atomic: false,
+            create_dirs: false,
             _state: PhantomData
         }
     }
 
     pub fn write_to(&self, path: impl Into<PathBuf>) -> FileClient<Write> {
-        FileClient {
-            path: Some(path.into()),
-            _state: PhantomData
-        }
+        self.with_state(path)
+    }
+
+    pub fn append_to(&self, path: impl Into<PathBuf>) -> FileClient<Append> {
+        self.with_state(path)
     }
 
     pub fn read_from(&self, path: impl Into<PathBuf>) -> FileClient<Read> {
-        FileClient {
-            path: Some(path.into()),
-            _state: PhantomData
-        }
+        self.with_state(path)
     }
 
     pub fn copy_from(&self, path: impl Into<PathBuf>) -> FileClient<Copy> {
-        FileClient {
-            path: Some(path.into()),
-            _state: PhantomData
-        }
+        self.with_state(path)
     }
 
     pub fn move_from(&self, path: impl Into<PathBuf>) -> FileClient<Move> {
+        self.with_state(path)
+    }
+
+    /// Creates a client in another state for the path, with the default options.
+    fn with_state<S>(&self, path: impl Into<PathBuf>) -> FileClient<S> {
         FileClient {
             path: Some(path.into()),
+            separator: Some(Bytes::from_static(NEWLINE)),
+            header_line: None,
+            atomic: false,
+            create_dirs: false,
             _state: PhantomData
         }
     }
@@ -60,9 +78,136 @@ impl FileClient<Empty> {
     }
 }
 
+impl<State> FileClient<State> {
+    /// Creates the missing parent directories of the path when enabled.
+    async fn create_parent_dirs(&self) -> anyhow::Result<()> {
+        if let Some(parent) = self.path.as_ref().unwrap().parent() && self.create_dirs && !parent.as_os_str().is_empty() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        Ok(())
+    }
+}
+
 impl FileClient<Write> {
+    /// Writes to a temporary file in the same directory which is renamed to the path once complete.
+    /// 
+    /// Readers of the path, such as a process polling the directory, then see either the previous or the complete new
+    /// content and never a partially written file. The temporary file is removed if the write fails. Disabled by default.
+    pub fn atomic(mut self, atomic: bool) -> Self {
+        self.atomic = atomic;
+        self
+    }
+
+    /// Creates the missing parent directories of the path before writing. Disabled by default.
+    pub fn create_dirs(mut self, create_dirs: bool) -> Self {
+        self.create_dirs = create_dirs;
+        self
+    }
+
+    pub async fn from_bytes(&self, bytes: impl Into<Bytes>) -> anyhow::Result<()> {
+        let bytes = bytes.into();
+        self.write(async |file| {
+            file.write_all(&bytes).await?;
+            Ok(())
+        }).await
+    }
+
+    /// Writes the bytes to the file, returning the checksum of the content as lowercase hex.
+    pub async fn from_bytes_with_checksum(&self, bytes: impl Into<Bytes>, algorithm: ChecksumAlgorithm) -> anyhow::Result<String> {
+        let bytes = bytes.into();
+        self.from_bytes(bytes.clone()).await?;
+
+        let mut hasher = ChecksumHasher::new(algorithm);
+        hasher.update(&bytes);
+        Ok(hasher.finalize_hex())
+    }
+
+    pub async fn from_stream(&self, stream: ByteStream) -> anyhow::Result<()> {
+        self.write_stream(stream, None).await
+    }
+
+    /// Writes the stream to the file, returning the checksum of the content as lowercase hex.
+    /// 
+    /// The checksum is computed while writing so the file is not read again.
+    pub async fn from_stream_with_checksum(&self, stream: ByteStream, algorithm: ChecksumAlgorithm) -> anyhow::Result<String> {
+        let mut hasher = ChecksumHasher::new(algorithm);
+        self.write_stream(stream, Some(&mut hasher)).await?;
+        Ok(hasher.finalize_hex())
+    }
+
+    async fn write_stream(&self, mut stream: ByteStream, mut hasher: Option<&mut ChecksumHasher>) -> anyhow::Result<()> {
+        self.write(async |file| {
+            while let Some(chunk) = stream.next().await {
+                let chunk = chunk?;
+                file.write_all(&chunk).await?;
+                if let Some(hasher) = hasher.as_mut() {
+                    hasher.update(&chunk);
+                }
+            }
+            Ok(())
+        }).await
+    }
+
+    async fn write(&self, write: impl AsyncFnOnce(&mut tokio::fs::File) -> anyhow::Result<()>) -> anyhow::Result<()> {
+        let path = self.path.as_ref().unwrap();
+        self.create_parent_dirs().await?;
+        if !self.atomic {
+            let mut file = tokio::fs::File::create(path).await?;
+            write(&mut file).await?;
+            file.flush().await?;
+            return Ok(());
+        }
+
+        let temp_path = Self::temp_path(path);
+        let result = async {
+            let mut file = tokio::fs::File::create(&temp_path).await?;
+            write(&mut file).await?;
+            file.flush().await?;
+            file.sync_all().await?;
+            tokio::fs::rename(&temp_path, path).await?;
+            anyhow::Ok(())
+        }.await;
+
+        if result.is_err() {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+        }
+        result
+    }
+
+    /// Returns a hidden, unique path next to the path, so the rename stays on the same filesystem.
+    fn temp_path(path: &Path) -> PathBuf {
+        let id = RandomState::new().build_hasher().finish();
+        let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        path.with_file_name(format!(".{}.{:016x}.tmp", name, id))
+    }
+}
+
+impl FileClient<Append> {
+    /// Sets the separator written before each appended record when the file already has content, defaults to the platform newline.
+    /// 
+    /// Use `None` to append the content exactly as is, e.g. when appending binary data.
+    pub fn line_separator(mut self, separator: Option<Vec<u8>>) -> Self {
+        self.separator = separator.map(Bytes::from);
+        self
+    }
+
+    /// Sets a header line that is written once when the file is newly created or empty, such as a CSV header.
+    /// 
+    /// The header line always ends with the platform newline, whatever the separator.
+    pub fn header_line(mut self, header_line: impl Into<Bytes>) -> Self {
+        self.header_line = Some(header_line.into());
+        self
+    }
+
+    /// Creates the missing parent directories of the path before appending. Disabled by default.
+    pub fn create_dirs(mut self, create_dirs: bool) -> Self {
+        self.create_dirs = create_dirs;
+        self
+    }
+
     pub async fn from_bytes(&self, bytes: impl Into<Bytes>) -> anyhow::Result<()> {
-        let mut file = tokio::fs::File::create(&self.path.as_ref().unwrap()).await?;
+        let mut file = self.open_append().await?;
         file.write_all(&bytes.into()).await?;
         file.flush().await?;
 
@@ -70,7 +215,7 @@ impl FileClient<Write> {
     }
 
     pub async fn from_stream(&self, mut stream: ByteStream) -> anyhow::Result<()> {
-        let mut file = tokio::fs::File::create(&self.path.as_ref().unwrap()).await?;
+        let mut file = self.open_append().await?;
 
         while let Some(chunk) = stream.next().await {
             let chunk = chunk?;
@@ -80,6 +225,24 @@ impl FileClient<Write> {
         file.flush().await?;
         Ok(())
     }
+
+    async fn open_append(&self) -> anyhow::Result<tokio::fs::File> {
+        self.create_parent_dirs().await?;
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path.as_ref().unwrap()).await?;
+        let len = file.metadata().await?.len();
+
+        if len == 0 && let Some(header_line) = &self.header_line {
+            file.write_all(header_line).await?;
+            file.write_all(NEWLINE).await?;
+            return Ok(file);
+        }
+
+        if len > 0 && let Some(separator) = &self.separator {
+            file.write_all(separator).await?;
+        }
+
+        Ok(file)
+    }
 }
 
 impl FileClient<Read> {
@@ -91,24 +254,55 @@ impl FileClient<Read> {
         Ok(Bytes::from(buffer))
     }
 
+    /// Returns the file as a stream with the content-length of the file.
     pub async fn as_stream(&self) -> anyhow::Result<ByteStream> {
         let file = tokio::fs::File::open(&self.path.as_ref().unwrap()).await?;
+        let content_length = file.metadata().await?.len();
         let reader = ReaderStream::new(file);
 
-        Ok(ByteStream::new(reader))
+        Ok(ByteStream::new(reader).with_content_length(content_length))
     }
 }
 
 impl FileClient<Copy> {
+    /// Copies the file to the path, on Linux the copy is done in-kernel using `copy_file_range` when supported.
     pub async fn copy_to(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
         tokio::fs::copy(&self.path.as_ref().unwrap(), path.as_ref()).await?;
 
         Ok(())
     }
+
+    /// Copies the file to the path, returning the checksum of the content as lowercase hex.
+    /// 
+    /// The checksum is computed while copying so the file is not read again, but the copy is then not done in-kernel.
+    pub async fn copy_to_with_checksum(&self, path: impl AsRef<Path>, algorithm: ChecksumAlgorithm) -> anyhow::Result<String> {
+        let mut hasher = ChecksumHasher::new(algorithm);
+        let mut source = tokio::fs::File::open(&self.path.as_ref().unwrap()).await?;
+        let mut file = tokio::fs::File::create(path.as_ref()).await?;
+        let mut buffer = vec![0u8; 64 * 1024];
+        loop {
+            let read = source.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            file.write_all(&buffer[..read]).await?;
+            hasher.update(&buffer[..read]);
+        }
+        file.flush().await?;
+
+        Ok(hasher.finalize_hex())
+    }
 }
 
 impl FileClient<Move> {
+    /// Moves the file to the path, renaming it when on the same filesystem and falling back to copy and delete otherwise.
     pub async fn move_to(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        match tokio::fs::rename(&self.path.as_ref().unwrap(), path.as_ref()).await {
+            Ok(_) => return Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::CrossesDevices => {},
+            Err(err) => return Err(err.into()),
+        }
+
         tokio::fs::copy(&self.path.as_ref().unwrap(), path.as_ref()).await?;
         tokio::fs::remove_file(&self.path.as_ref().unwrap()).await?;
 
@@ -1,4 +1,4 @@
-use crate::{common::stream::ByteStream, file::file_client::FileClient};
+use crate::{common::{checksum::ChecksumAlgorithm, stream::ByteStream}, file::file_client::FileClient};
 
 
 #[tokio::test(start_paused = true)]
@@ -16,7 +16,7 @@ async fn client_test() {
     assert!(result.is_ok());
 
     let result = client.read_from("/tmp/test.txt").as_stream().await;
-    assert!(result.is_ok());
+    assert_eq!(result.unwrap().content_length(), Some(6));
 
     let result = client.copy_from("/tmp/test.txt").copy_to("/tmp/test_copy.txt").await;
     assert!(result.is_ok());
@@ -26,4 +26,150 @@ async fn client_test() {
 
     let result = client.delete("/tmp/test_copy.txt").await;
     assert!(result.is_ok());
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn client_append_test() {
+    let client = FileClient::new();
+    let _ = client.delete("/tmp/test_append.csv").await;
+
+    let result = client.append_to("/tmp/test_append.csv").header_line("id,name").line_separator(Some(b"\n".to_vec())).from_bytes("1,a").await;
+    assert!(result.is_ok());
+
+    let result = client.append_to("/tmp/test_append.csv").header_line("id,name").line_separator(Some(b"\n".to_vec())).from_stream(ByteStream::from("2,b")).await;
+    assert!(result.is_ok());
+
+    let result = client.read_from("/tmp/test_append.csv").as_bytes().await;
+    assert_eq!(result.unwrap(), "id,name\n1,a\n2,b");
+
+    let result = client.delete("/tmp/test_append.csv").await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn client_move_test() {
+    let client = FileClient::new();
+
+    let result = client.write_to("/tmp/test_move.txt").from_bytes("move").await;
+    assert!(result.is_ok());
+
+    let result = client.move_from("/tmp/test_move.txt").move_to("/tmp/test_moved.txt").await;
+    assert!(result.is_ok());
+    assert!(!std::path::Path::new("/tmp/test_move.txt").exists());
+    assert_eq!(client.read_from("/tmp/test_moved.txt").as_bytes().await.unwrap(), "move");
+
+    let result = client.move_from("/tmp/test_move.txt").move_to("/tmp/test_moved.txt").await;
+    assert!(result.is_err());
+
+    let result = client.delete("/tmp/test_moved.txt").await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn client_atomic_write_test() {
+    let client = FileClient::new();
+    let path = "/tmp/test_atomic.bin";
+    let _ = client.delete(path).await;
+    let size = 8 * 1024 * 1024;
+
+    let watcher = tokio::spawn(async move {
+        let mut observed = Vec::new();
+        for _ in 0..2000 {
+            if let Ok(metadata) = tokio::fs::metadata(path).await {
+                observed.push(metadata.len());
+            }
+            tokio::task::yield_now().await;
+        }
+        observed
+    });
+
+    let chunks = (0..128).map(|_| Ok::<_, std::io::Error>(bytes::Bytes::from(vec![7u8; 64 * 1024])));
+    let result = client.write_to(path).atomic(true).from_stream(ByteStream::new(futures::stream::iter(chunks))).await;
+    assert!(result.is_ok());
+
+    let observed = watcher.await.unwrap();
+    assert!(observed.iter().all(|len| *len == size));
+    assert_eq!(client.read_from(path).as_bytes().await.unwrap().len() as u64, size);
+
+    let entries: Vec<_> = std::fs::read_dir("/tmp").unwrap().filter_map(|entry| entry.ok()).filter(|entry| entry.file_name().to_string_lossy().starts_with(".test_atomic.bin.")).collect();
+    assert!(entries.is_empty());
+
+    let result = client.delete(path).await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn client_create_dirs_test() {
+    let client = FileClient::new();
+    let _ = tokio::fs::remove_dir_all("/tmp/test_create_dirs").await;
+
+    let result = client.write_to("/tmp/test_create_dirs/2024/06/report.txt").from_bytes("report").await;
+    assert!(result.is_err());
+
+    let result = client.write_to("/tmp/test_create_dirs/2024/06/report.txt").create_dirs(true).from_bytes("report").await;
+    assert!(result.is_ok());
+    assert_eq!(client.read_from("/tmp/test_create_dirs/2024/06/report.txt").as_bytes().await.unwrap(), "report");
+
+    let result = client.append_to("/tmp/test_create_dirs/2024/07/log.txt").create_dirs(true).from_bytes("line").await;
+    assert!(result.is_ok());
+    assert_eq!(client.read_from("/tmp/test_create_dirs/2024/07/log.txt").as_bytes().await.unwrap(), "line");
+
+    let result = tokio::fs::remove_dir_all("/tmp/test_create_dirs").await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn client_checksum_test() {
+    let client = FileClient::new();
+
+    let result = client.write_to("/tmp/test_checksum.txt").from_bytes_with_checksum("hello world", ChecksumAlgorithm::Sha256).await;
+    assert_eq!(result.unwrap(), "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9");
+
+    let stream = ByteStream::new(futures::stream::iter(["hello", " ", "world"].map(|chunk| Ok::<_, std::io::Error>(bytes::Bytes::from(chunk)))));
+    let result = client.write_to("/tmp/test_checksum.txt").from_stream_with_checksum(stream, ChecksumAlgorithm::Md5).await;
+    assert_eq!(result.unwrap(), "5eb63bbbe01eeed093cb22bb8f5acdc3");
+
+    let result = client.copy_from("/tmp/test_checksum.txt").copy_to_with_checksum("/tmp/test_checksum_copy.txt", ChecksumAlgorithm::Sha256).await;
+    assert_eq!(result.unwrap(), "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9");
+    assert_eq!(client.read_from("/tmp/test_checksum_copy.txt").as_bytes().await.unwrap(), "hello world");
+
+    let result = client.delete("/tmp/test_checksum.txt").await;
+    assert!(result.is_ok());
+
+    let result = client.delete("/tmp/test_checksum_copy.txt").await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn client_append_binary_test() {
+    let client = FileClient::new();
+    let _ = client.delete("/tmp/test_append.bin").await;
+
+    let result = client.append_to("/tmp/test_append.bin").line_separator(None).from_bytes(vec![0u8, 1, 2]).await;
+    assert!(result.is_ok());
+
+    let result = client.append_to("/tmp/test_append.bin").line_separator(None).from_bytes(vec![3u8, 4]).await;
+    assert!(result.is_ok());
+
+    let result = client.append_to("/tmp/test_append.bin").line_separator(Some(vec![0xff, 0xff])).from_bytes(vec![5u8]).await;
+    assert!(result.is_ok());
+
+    let result = client.read_from("/tmp/test_append.bin").as_bytes().await;
+    assert_eq!(result.unwrap(), vec![0u8, 1, 2, 3, 4, 0xff, 0xff, 5]);
+
+    let result = client.delete("/tmp/test_append.bin").await;
+    assert!(result.is_ok());
+
+    let newline = if cfg!(windows) { "\r\n" } else { "\n" };
+    let result = client.append_to("/tmp/test_append.bin").header_line("id,name").line_separator(None).from_bytes("1,a").await;
+    assert!(result.is_ok());
+
+    let result = client.append_to("/tmp/test_append.bin").header_line("id,name").line_separator(None).from_bytes("2,b").await;
+    assert!(result.is_ok());
+
+    let result = client.read_from("/tmp/test_append.bin").as_bytes().await;
+    assert_eq!(result.unwrap(), format!("id,name{}1,a2,b", newline));
+
+    let result = client.delete("/tmp/test_append.bin").await;
+    assert!(result.is_ok());
+}
@@ -1,4 +1,6 @@
-use rustls::{ClientConfig, RootCertStore};
+use std::{path::Path, sync::Arc};
+
+use rustls::{ClientConfig, RootCertStore, client::Resumption, crypto::CryptoProvider, sign::{CertifiedKey, SingleCertAndKey}};
 use webpki_roots::TLS_SERVER_ROOTS;
 
 use crate::http::crypto::Crypto;
@@ -14,28 +16,98 @@ impl HttpClientConfig {
     /// [`webpki_roots`](https://docs.rs/webpki-roots) crate.
     /// 
     pub fn new() -> Self {
+        Self::builder().build()
+    }
+
+    /// Creates a new instance trusting only the Mozilla root certificates, skipping the scan of the system cert store.
+    /// 
+    /// Useful in minimal containers lacking a cert store.
+    pub fn minimal() -> Self {
+        Self::builder().load_native_certs(false).build()
+    }
+
+    pub fn builder() -> HttpClientConfigBuilder {
+        HttpClientConfigBuilder {
+            load_native_certs: true,
+            client_cert: None,
+        }
+    }
+
+    fn with_native_certs(load_native_certs: bool, client_cert: Option<Arc<CertifiedKey>>) -> Self {
         let mut root_cert_store = RootCertStore::empty();
         root_cert_store.extend(TLS_SERVER_ROOTS.iter().cloned());
-        let native_certs = rustls_native_certs::load_native_certs();
-        for cert in native_certs.certs {
-            root_cert_store.add(cert).unwrap();
-        }
-        for error in native_certs.errors {
-            tracing::warn!("failed to load native cert: {:?}", error);
+        if load_native_certs {
+            let native_certs = rustls_native_certs::load_native_certs();
+            for cert in native_certs.certs {
+                root_cert_store.add(cert).unwrap();
+            }
+            for error in native_certs.errors {
+                tracing::warn!("failed to load native cert: {:?}", error);
+            }
         }
 
         if let Err(error) = Crypto::install_crypto_provider() {
             tracing::warn!("failed to install crypto provider: {:?}", error);
         }
 
-        let mut tls_config = ClientConfig::builder()
-        .with_root_certificates(root_cert_store.clone())
-        .with_no_client_auth();
+        let tls_config = ClientConfig::builder().with_root_certificates(root_cert_store.clone());
+        let mut tls_config = match client_cert {
+            Some(client_cert) => tls_config.with_client_cert_resolver(Arc::new(SingleCertAndKey::from(client_cert))),
+            None => tls_config.with_no_client_auth(),
+        };
 
         tls_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        tls_config.resumption = Resumption::in_memory_sessions(256);
 
         HttpClientConfig {
             tls_config,
         }
     }
-}
\ No newline at end of file
+
+    /// Enables or disables TLS session resumption using an in-memory session cache holding up to `cache_size` sessions.
+    /// 
+    /// Resumed sessions skip the full handshake when reconnecting to the same host with a client sharing this config.
+    /// Enabled with a cache size of 256 by default.
+    pub fn session_resumption(mut self, enabled: bool, cache_size: usize) -> Self {
+        self.tls_config.resumption = match enabled {
+            true => Resumption::in_memory_sessions(cache_size),
+            false => Resumption::disabled(),
+        };
+        self
+    }
+}
+
+pub struct HttpClientConfigBuilder {
+    load_native_certs: bool,
+    client_cert: Option<Arc<CertifiedKey>>,
+}
+
+impl HttpClientConfigBuilder {
+    /// Enables or disables loading the system native root certs in addition to the Mozilla root certificates.
+    /// 
+    /// Enabled by default.
+    pub fn load_native_certs(mut self, enabled: bool) -> Self {
+        self.load_native_certs = enabled;
+        self
+    }
+
+    /// Authenticates the client with a certificate chain and private key in `.pem` format, for servers requiring mutual TLS.
+    /// 
+    /// Returns an error if the files can not be loaded or the private key does not match the certificate.
+    pub fn client_cert(mut self, cert_path: impl AsRef<Path>, key_path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let certs = Crypto::pem_load_certs(cert_path)?;
+        let key = Crypto::pem_load_private_key(key_path)?;
+        if let Err(error) = Crypto::install_crypto_provider() {
+            tracing::warn!("failed to install crypto provider: {:?}", error);
+        }
+
+        let provider = CryptoProvider::get_default().ok_or_else(|| anyhow::anyhow!("No crypto provider installed."))?;
+        let client_cert = CertifiedKey::from_der(certs, key, provider).map_err(|error| anyhow::anyhow!("Invalid client cert or key: {}", error))?;
+        self.client_cert = Some(Arc::new(client_cert));
+        Ok(self)
+    }
+
+    pub fn build(self) -> HttpClientConfig {
+        HttpClientConfig::with_native_certs(self.load_native_certs, self.client_cert)
+    }
+}
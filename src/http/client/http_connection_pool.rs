@@ -0,0 +1,92 @@
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use bytes::Bytes;
+use http_body_util::combinators::UnsyncBoxBody;
+use hyper::{Request, Response, body::Incoming, client::conn::{http1, http2}};
+use tokio::time::Instant;
+
+pub(crate) type PoolKey = (String, String, u16);
+pub(crate) type PoolBody = UnsyncBoxBody<Bytes, Box<dyn std::error::Error + Send + Sync>>;
+
+/// A connection handle, HTTP/1.1 connections serve one request at a time while HTTP/2 connections are shared.
+pub(crate) enum PooledSender {
+    Http1(http1::SendRequest<PoolBody>),
+    Http2(http2::SendRequest<PoolBody>),
+}
+
+impl PooledSender {
+    pub(crate) async fn send_request(&mut self, request: Request<PoolBody>) -> hyper::Result<Response<Incoming>> {
+        match self {
+            PooledSender::Http1(sender) => sender.send_request(request).await,
+            PooledSender::Http2(sender) => sender.send_request(request).await,
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        match self {
+            PooledSender::Http1(sender) => sender.is_ready(),
+            PooledSender::Http2(sender) => sender.is_ready(),
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        match self {
+            PooledSender::Http1(sender) => sender.is_closed(),
+            PooledSender::Http2(sender) => sender.is_closed(),
+        }
+    }
+}
+
+struct IdleConnection {
+    sender: PooledSender,
+    idle_since: Instant,
+}
+
+/// Keeps idle connections per scheme, host and port for reuse by clones of a client.
+pub(crate) struct ConnectionPool {
+    max_idle_per_host: usize,
+    idle_timeout: Duration,
+    idle: Mutex<HashMap<PoolKey, Vec<IdleConnection>>>,
+}
+
+impl ConnectionPool {
+    pub(crate) fn new(max_idle_per_host: usize, idle_timeout: Duration) -> Self {
+        ConnectionPool {
+            max_idle_per_host,
+            idle_timeout,
+            idle: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes a ready connection for the key, dropping closed and expired connections.
+    ///
+    /// HTTP/2 connections stay in the pool and a handle to the shared connection is returned.
+    pub(crate) fn checkout(&self, key: &PoolKey) -> Option<PooledSender> {
+        let mut idle = self.idle.lock().unwrap();
+        let connections = idle.get_mut(key)?;
+        connections.retain(|connection| !connection.sender.is_closed() && connection.idle_since.elapsed() < self.idle_timeout);
+
+        let index = connections.iter().position(|connection| connection.sender.is_ready())?;
+        match &connections[index].sender {
+            PooledSender::Http2(sender) => {
+                let sender = sender.clone();
+                connections[index].idle_since = Instant::now();
+                Some(PooledSender::Http2(sender))
+            },
+            PooledSender::Http1(_) => Some(connections.remove(index).sender),
+        }
+    }
+
+    /// Returns a connection to the pool, it is dropped and closed if the pool for the key is full.
+    ///
+    /// HTTP/1.1 connections become ready for the next request once the response body has been read.
+    pub(crate) fn checkin(&self, key: PoolKey, sender: PooledSender) {
+        let mut idle = self.idle.lock().unwrap();
+        let connections = idle.entry(key).or_default();
+        let shared = matches!(sender, PooledSender::Http2(_)) && connections.iter().any(|connection| matches!(connection.sender, PooledSender::Http2(_)));
+
+        if !shared && connections.len() < self.max_idle_per_host {
+            connections.push(IdleConnection { sender, idle_since: Instant::now() });
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use std::time::{Duration, Instant};
+
+/// Durations of the phases of a request, recorded by clients created with [`with_timing`](crate::http::client::http_client::HttpClient::with_timing).
+///
+/// All phases end when the response head is received, reading the body is not included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimingInfo {
+    /// Time to resolve the host and open the connection, including a proxy tunnel. Zero for reused pooled connections.
+    pub connect: Duration,
+    /// Time of the TLS handshake, `None` for plain connections and reused pooled connections.
+    pub tls_handshake: Option<Duration>,
+    /// Time from sending the request until the response head was received.
+    pub time_to_first_byte: Duration,
+    /// Time from the start of the request until the response head was received.
+    pub total: Duration,
+}
+
+/// Records the instants of each phase, does nothing when timing is disabled.
+pub(crate) struct TimingRecorder {
+    start: Option<Instant>,
+    connected: Option<Instant>,
+    handshaken: Option<Instant>,
+    sent: Option<Instant>,
+}
+
+impl TimingRecorder {
+    pub(crate) fn new(enabled: bool) -> Self {
+        TimingRecorder {
+            start: enabled.then(Instant::now),
+            connected: None,
+            handshaken: None,
+            sent: None,
+        }
+    }
+
+    pub(crate) fn connected(&mut self) {
+        self.connected = self.start.map(|_| Instant::now());
+    }
+
+    pub(crate) fn handshaken(&mut self) {
+        self.handshaken = self.start.map(|_| Instant::now());
+    }
+
+    pub(crate) fn sent(&mut self) {
+        self.sent = self.start.map(|_| Instant::now());
+    }
+
+    /// Returns the timing once the response head has been received, or `None` when disabled.
+    pub(crate) fn finish(&self) -> Option<TimingInfo> {
+        let start = self.start?;
+        let now = Instant::now();
+        let sent = self.sent.unwrap_or(start);
+
+        Some(TimingInfo {
+            connect: self.connected.map(|connected| connected - start).unwrap_or_default(),
+            tls_handshake: self.handshaken.zip(self.connected).map(|(handshaken, connected)| handshaken - connected),
+            time_to_first_byte: now - sent,
+            total: now - start,
+        })
+    }
+}
@@ -1,4 +1,10 @@
 #[cfg(feature = "http")]
 pub mod http_client;
 #[cfg(feature = "http")]
-pub mod http_client_config;
\ No newline at end of file
+pub mod http_client_config;
+#[cfg(feature = "http")]
+pub mod http_timing;
+#[cfg(feature = "http")]
+mod http_connection_pool;
+#[cfg(feature = "http")]
+mod http_proxy;
\ No newline at end of file
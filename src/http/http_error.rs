@@ -0,0 +1,45 @@
+use std::{fmt, time::Duration};
+
+/// Errors returned by the [`HttpClient`](crate::http::client::http_client::HttpClient), categorised so callers can decide what to retry.
+#[derive(Debug)]
+pub enum HttpError {
+    /// The url is missing a scheme or host, uses an unsupported scheme or has an invalid host name.
+    InvalidUrl(String),
+    /// Connecting to the server failed, including name resolution. No bytes of the request were sent.
+    Connect(std::io::Error),
+    /// The TLS handshake failed. No bytes of the request were sent.
+    Tls(std::io::Error),
+    /// No response head was received within the configured timeout.
+    Timeout(Duration),
+    /// The HTTP exchange failed, e.g. the connection was closed or the response was malformed.
+    Protocol(hyper::Error),
+    /// Reading the request body failed.
+    Body(anyhow::Error),
+    /// The server responded with a non-success status, see [`HttpResponse::error_for_status`](crate::http::http_response::HttpResponse::error_for_status).
+    Status(u16),
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HttpError::InvalidUrl(reason) => write!(f, "Invalid URL: {}", reason),
+            HttpError::Connect(err) => write!(f, "Connection failed: {}", err),
+            HttpError::Tls(err) => write!(f, "TLS handshake failed: {}", err),
+            HttpError::Timeout(timeout) => write!(f, "Request timed out after {:?}", timeout),
+            HttpError::Protocol(err) => write!(f, "HTTP protocol error: {}", err),
+            HttpError::Body(err) => write!(f, "Request body error: {}", err),
+            HttpError::Status(status) => write!(f, "Unsuccessful response status: {}", status),
+        }
+    }
+}
+
+impl std::error::Error for HttpError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            HttpError::Connect(err) | HttpError::Tls(err) => Some(err),
+            HttpError::Protocol(err) => Some(err),
+            HttpError::Body(err) => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use std::{collections::hash_map::RandomState, hash::BuildHasher, str::FromStr};
+
+use bytes::{Bytes, BytesMut};
+use hyper::{HeaderMap, header::{HeaderName, HeaderValue}};
+
+#[derive(Debug, Clone)]
+pub struct MultipartPart {
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl MultipartPart {
+    /// Parses a `multipart/*` body into its parts using the boundary from the content-type.
+    ///
+    /// Returns an error if the content-type is not `multipart/*`, the boundary is missing or the body is malformed.
+    pub fn parse(content_type: &str, body: &Bytes) -> anyhow::Result<Vec<MultipartPart>> {
+        let boundary = Self::boundary(content_type)?;
+        let delimiter = format!("--{}", boundary).into_bytes();
+        let mut parts = Vec::new();
+
+        // The first delimiter must be at the start of the body or preceded by CRLF, anything before it is preamble.
+        let mut position = match Self::find(body, &delimiter, 0) {
+            Some(0) => 0,
+            _ => {
+                let mut crlf_delimiter = b"\r\n".to_vec();
+                crlf_delimiter.extend_from_slice(&delimiter);
+                Self::find(body, &crlf_delimiter, 0)
+                    .map(|index| index + 2)
+                    .ok_or_else(|| anyhow::anyhow!("Malformed multipart body: boundary not found"))?
+            }
+        };
+
+        let mut next_delimiter = b"\r\n".to_vec();
+        next_delimiter.extend_from_slice(&delimiter);
+
+        loop {
+            position += delimiter.len();
+            // Tolerate a closing delimiter missing the trailing `--` at the end of the body.
+            if body[position..].starts_with(b"--") || body[position..].iter().all(|byte| byte.is_ascii_whitespace()) {
+                return Ok(parts);
+            }
+
+            // Skip optional transport padding before the line break.
+            while position < body.len() && (body[position] == b' ' || body[position] == b'\t') {
+                position += 1;
+            }
+
+            if !body[position..].starts_with(b"\r\n") {
+                return Err(anyhow::anyhow!("Malformed multipart body: expected line break after boundary"));
+            }
+            position += 2;
+
+            let end = Self::find(body, &next_delimiter, position).ok_or_else(|| anyhow::anyhow!("Malformed multipart body: closing boundary not found"))?;
+            parts.push(Self::parse_part(body.slice(position..end))?);
+            position = end + 2;
+        }
+    }
+
+    /// Returns the headers of the part.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Returns a single header by key.
+    pub fn header(&self, key: impl AsRef<str>) -> Option<&HeaderValue> {
+        self.headers.get(key.as_ref())
+    }
+
+    /// Returns the content-type of the part, if any.
+    pub fn content_type(&self) -> Option<&str> {
+        self.headers.get("content-type").and_then(|value| value.to_str().ok())
+    }
+
+    /// Returns the field name from the `content-disposition` header of a `multipart/form-data` part.
+    pub fn name(&self) -> Option<String> {
+        self.disposition_param("name")
+    }
+
+    /// Returns the file name from the `content-disposition` header, if the part is a file.
+    pub fn file_name(&self) -> Option<String> {
+        self.disposition_param("filename")
+    }
+
+    /// Returns the body of the part.
+    pub fn body(&self) -> &Bytes {
+        &self.body
+    }
+
+    /// Parses a nested `multipart/*` part into its own parts.
+    pub fn multipart(&self) -> anyhow::Result<Vec<MultipartPart>> {
+        let content_type = self.content_type().ok_or_else(|| anyhow::anyhow!("Part is missing a content-type"))?;
+        Self::parse(content_type, &self.body)
+    }
+
+    fn disposition_param(&self, key: &str) -> Option<String> {
+        let disposition = self.header("content-disposition")?.to_str().ok()?;
+        disposition.split(';').skip(1).find_map(|param| {
+            let (name, value) = param.split_once('=')?;
+            if !name.trim().eq_ignore_ascii_case(key) {
+                return None;
+            }
+
+            let value = value.trim();
+            let value = value.strip_prefix('"').and_then(|value| value.strip_suffix('"')).unwrap_or(value);
+            Some(value.replace("%22", "\"").replace("%0D", "\r").replace("%0A", "\n"))
+        })
+    }
+
+    fn parse_part(part: Bytes) -> anyhow::Result<MultipartPart> {
+        let (header_bytes, body) = if part.starts_with(b"\r\n") {
+            (Bytes::new(), part.slice(2..))
+        } else {
+            let index = Self::find(&part, b"\r\n\r\n", 0).ok_or_else(|| anyhow::anyhow!("Malformed multipart body: part headers not terminated"))?;
+            (part.slice(..index), part.slice(index + 4..))
+        };
+
+        let mut headers = HeaderMap::new();
+        let header_str = std::str::from_utf8(&header_bytes)?;
+        for line in header_str.split("\r\n").filter(|line| !line.is_empty()) {
+            let (key, value) = line.split_once(':').ok_or_else(|| anyhow::anyhow!("Malformed multipart header: {}", line))?;
+            let key = HeaderName::from_str(key.trim())?;
+            let value = HeaderValue::from_str(value.trim())?;
+            headers.append(key, value);
+        }
+
+        Ok(MultipartPart {
+            headers,
+            body,
+        })
+    }
+
+    fn boundary(content_type: &str) -> anyhow::Result<String> {
+        let mut params = content_type.split(';');
+        let mime = params.next().unwrap_or_default().trim();
+        if !mime.to_ascii_lowercase().starts_with("multipart/") {
+            return Err(anyhow::anyhow!("Content-type is not multipart: {}", mime));
+        }
+
+        for param in params {
+            if let Some((key, value)) = param.split_once('=') && key.trim().eq_ignore_ascii_case("boundary") {
+                let value = value.trim();
+                let boundary = match value.strip_prefix('"').and_then(|value| value.strip_suffix('"')) {
+                    Some(quoted) => quoted.replace("\\\"", "\"").replace("\\\\", "\\"),
+                    None => value.to_string(),
+                };
+
+                if boundary.is_empty() || boundary.len() > 70 {
+                    return Err(anyhow::anyhow!("Invalid multipart boundary: {}", boundary));
+                }
+
+                return Ok(boundary);
+            }
+        }
+
+        Err(anyhow::anyhow!("Multipart content-type is missing a boundary"))
+    }
+
+    fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+        if from > haystack.len() {
+            return None;
+        }
+        haystack[from..].windows(needle.len()).position(|window| window == needle).map(|index| index + from)
+    }
+}
+
+/// Builder for `multipart/form-data` bodies as described by RFC 7578, with text fields and file parts.
+#[derive(Debug, Clone)]
+pub struct MultipartForm {
+    boundary: String,
+    parts: Vec<(String, Bytes)>,
+}
+
+impl MultipartForm {
+    /// Creates an empty form with a random boundary.
+    pub fn new() -> Self {
+        let random = RandomState::new();
+        MultipartForm {
+            boundary: format!("{:016x}{:016x}", random.hash_one(0u8), random.hash_one(1u8)),
+            parts: Vec::new(),
+        }
+    }
+
+    /// Adds a text field.
+    pub fn text(mut self, name: impl AsRef<str>, value: impl AsRef<str>) -> Self {
+        let headers = format!("Content-Disposition: form-data; name=\"{}\"\r\n", Self::escape(name.as_ref()));
+        self.parts.push((headers, Bytes::copy_from_slice(value.as_ref().as_bytes())));
+        self
+    }
+
+    /// Adds a file part with a file name and content-type, e.g. `application/pdf`.
+    /// 
+    /// Fails if the content-type contains control characters such as CR or LF.
+    pub fn file(mut self, name: impl AsRef<str>, file_name: impl AsRef<str>, content_type: impl AsRef<str>, body: impl Into<Bytes>) -> anyhow::Result<Self> {
+        if content_type.as_ref().chars().any(char::is_control) {
+            anyhow::bail!("Invalid content-type: {:?}", content_type.as_ref());
+        }
+
+        let headers = format!(
+            "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n",
+            Self::escape(name.as_ref()),
+            Self::escape(file_name.as_ref()),
+            content_type.as_ref()
+        );
+        self.parts.push((headers, body.into()));
+        Ok(self)
+    }
+
+    /// Returns the boundary separating the parts.
+    pub fn boundary(&self) -> &str {
+        &self.boundary
+    }
+
+    /// Returns the `multipart/form-data` content-type including the boundary.
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    /// Encodes the parts into the body.
+    pub fn to_bytes(&self) -> Bytes {
+        let mut body = BytesMut::new();
+        for (headers, part) in self.parts.iter() {
+            body.extend_from_slice(format!("--{}\r\n{}\r\n", self.boundary, headers).as_bytes());
+            body.extend_from_slice(part);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{}--\r\n", self.boundary).as_bytes());
+        body.freeze()
+    }
+
+    /// Percent-encodes quotes and line breaks in names as required by RFC 7578.
+    fn escape(value: &str) -> String {
+        value.replace('"', "%22").replace('\r', "%0D").replace('\n', "%0A")
+    }
+}
+
+impl Default for MultipartForm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,4 +1,5 @@
 use std::marker::PhantomData;
+use std::net::SocketAddr;
 use std::str::FromStr;
 
 use anyhow::Error;
@@ -12,6 +13,7 @@ use hyper::header::{HeaderName, HeaderValue};
 use hyper::{Request, body::Incoming};
 
 use crate::common::stream::ByteStream;
+use crate::http::http_multipart::{MultipartForm, MultipartPart};
 
 pub struct Final;
 pub struct SetMethod;
@@ -21,6 +23,7 @@ pub struct HttpRequest {
     body: BoxBody<Bytes, Error>,
     parts: hyper::http::request::Parts,
     params: Vec<(String, String)>,
+    remote_addr: Option<SocketAddr>,
 }
 
 impl HttpRequest {
@@ -39,7 +42,8 @@ impl HttpRequest {
         HttpRequest {
             body,
             parts,
-            params: Vec::new()
+            params: Vec::new(),
+            remote_addr: None,
         }
     }
 
@@ -48,10 +52,16 @@ impl HttpRequest {
         HttpRequest {
             body,
             parts,
-            params
+            params,
+            remote_addr: None,
         }
     }
 
+    pub(crate) fn with_remote_addr(mut self, remote_addr: SocketAddr) -> Self {
+        self.remote_addr = Some(remote_addr);
+        self
+    }
+
     /// Returns the boxed body.
     /// 
     /// Used for moving body between requests/responses.
@@ -62,6 +72,75 @@ impl HttpRequest {
         ByteStream::new(stream)
     }
 
+    /// Collects the body and parses it as `multipart/*` into a list of parts using the boundary from the content-type.
+    ///
+    /// **This consumes the HttpRequest**
+    pub async fn multipart(self) -> anyhow::Result<Vec<MultipartPart>> {
+        let content_type = match self.header("content-type") {
+            Some(value) => value.to_str()?.to_string(),
+            None => return Err(anyhow::anyhow!("Request is missing a content-type")),
+        };
+
+        let body = self.body().to_bytes().await?;
+        MultipartPart::parse(&content_type, &body)
+    }
+
+    /// Buffers the body in memory, failing with [`BodyError::TooLarge`] when it exceeds `limit` bytes.
+    /// 
+    /// Returns the request with the buffered body restored along with a copy of the bytes.
+    pub(crate) async fn buffer_body(self, limit: usize) -> Result<(HttpRequest, Bytes), BodyError> {
+        let content_length = self.header("content-length").and_then(|value| value.to_str().ok()).and_then(|value| value.parse::<u64>().ok());
+        if content_length.is_some_and(|length| length > limit as u64) {
+            return Err(BodyError::TooLarge);
+        }
+
+        let mut body = self.body;
+        let mut buffer = Vec::new();
+        while let Some(frame) = body.frame().await {
+            let frame = match frame {
+                Ok(frame) => frame,
+                Err(err) if err.is::<http_body_util::LengthLimitError>() => return Err(BodyError::TooLarge),
+                Err(err) => return Err(BodyError::Other(err)),
+            };
+            let Ok(data) = frame.into_data() else {
+                continue;
+            };
+            if buffer.len() + data.len() > limit {
+                return Err(BodyError::TooLarge);
+            }
+            buffer.extend_from_slice(&data);
+        }
+
+        let bytes = Bytes::from(buffer);
+        let body = Full::from(bytes.clone()).map_err(|e| match e {}).boxed();
+        let request = HttpRequest {
+            body,
+            parts: self.parts,
+            params: self.params,
+            remote_addr: self.remote_addr,
+        };
+        Ok((request, bytes))
+    }
+
+    /// Collects the body and deserializes it from JSON.
+    ///
+    /// **This consumes the HttpRequest**
+    #[cfg(feature = "json")]
+    pub async fn json<T: serde::de::DeserializeOwned>(self) -> anyhow::Result<T> {
+        let body = self.body().to_bytes().await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    /// Collects the body and converts into a `http::Request<Bytes>` for interop with crates built on the `http` types.
+    ///
+    /// Route params are not carried over.
+    ///
+    /// **This consumes the HttpRequest**
+    pub async fn into_http_request(self) -> anyhow::Result<Request<Bytes>> {
+        let body = self.body.collect().await?.to_bytes();
+        Ok(Request::from_parts(self.parts, body))
+    }
+
     /// Returns the method.
     pub fn method(&self) -> &str {
         self.parts.method.as_str()
@@ -110,15 +189,39 @@ impl HttpRequest {
         &self.parts.headers
     }
 
-    // Returns a param by key.
+    /// Returns a route param by key, e.g. `id` for `/users/{id}` or `path` for the catch-all `/files/{*path}`.
+    /// 
+    /// Catch-all params contain the rest of the path without the leading slash, e.g. `a/b/c` for `/files/a/b/c`.
     pub fn param(&self, key: impl AsRef<str>) -> Option<&str> {
         self.params.iter().find(|(k, _)| k == key.as_ref()).map(|(_, v)| v.as_str())
     }
 
+    /// Returns a route param by key parsed into `T`, failing when the param is missing or can not be parsed.
+    pub fn param_parse<T>(&self, key: impl AsRef<str>) -> anyhow::Result<T>
+    where
+        T: FromStr,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        let key = key.as_ref();
+        let value = self.param(key).ok_or_else(|| anyhow::anyhow!("Missing route param {}", key))?;
+        Ok(value.parse::<T>()?)
+    }
+
     /// Returns an iterator with request params.
     pub fn params(&self) -> impl Iterator<Item = (&str, &str)>  {
         self.params.iter().map(|(k, v)| (k.as_str(), v.as_str()))
     }
+
+    /// Returns the address of the remote peer for requests received by the server.
+    pub fn remote_addr(&self) -> Option<SocketAddr> {
+        self.remote_addr
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum BodyError {
+    TooLarge,
+    Other(anyhow::Error),
 }
 
 pub struct HttpRequestBuilder<State> {
@@ -190,7 +293,19 @@ impl HttpRequestBuilder<SetMethod> {
 
     /// Sets the HTTP method to `HEAD` and assigns the request URI.
     pub fn head(mut self, uri: impl Into<String>) -> HttpRequestBuilder<Final> {
-        self.builder = self.builder.method("OPTIONS");
+        self.builder = self.builder.method("HEAD");
+        HttpRequestBuilder {
+            builder: self.builder,
+            uri_string: Some(uri.into()),
+            _state: PhantomData
+        }
+    }
+
+    /// Sets any HTTP method, including extension methods such as WebDAV `PROPFIND` or `MKCOL`, and assigns the request URI.
+    /// 
+    /// The method is case-sensitive and sent as given, building the request fails if it is not a valid method token.
+    pub fn method(mut self, method: impl AsRef<str>, uri: impl Into<String>) -> HttpRequestBuilder<Final> {
+        self.builder = self.builder.method(method.as_ref());
         HttpRequestBuilder {
             builder: self.builder,
             uri_string: Some(uri.into()),
@@ -223,27 +338,43 @@ impl HttpRequestBuilder<Final> {
 
     /// Finish the builder and the create the request with an empty body.
     pub fn body_empty(self) -> anyhow::Result<HttpRequest> {
-        let uri: Uri = self.uri_string.unwrap().parse()?;
         let body = Empty::new().map_err(|e| match e {}).boxed();
-        let request = self.builder.uri(&uri).header("Host", uri.host().unwrap()).body(body)?;
-        Ok(HttpRequest::from(request))
+        self.build(body)
     }
 
     /// Finish the builder and the create the request with a body of bytes in memory.
     pub fn body_bytes(self, body: impl Into<Bytes>) -> anyhow::Result<HttpRequest> {
-        let uri: Uri = self.uri_string.unwrap().parse()?;
         let body = Full::from(body.into()).map_err(|e| match e {}).boxed();
-        let request = self.builder.uri(&uri).header("Host", uri.host().unwrap()).body(body)?;
-        Ok(HttpRequest::from(request))
+        self.build(body)
     }
 
     /// Finish the builder and the create the request with a body of bytes as a stream.
     pub fn body_stream(self, stream: ByteStream) -> anyhow::Result<HttpRequest> {
-        let uri: Uri = self.uri_string.unwrap().parse()?;
         let mapped_stream = stream.inner_stream().map(|res| { res.map(Frame::data) });
         let body = StreamBody::new(mapped_stream);
         let boxed_body: BoxBody<Bytes, anyhow::Error> = BodyExt::boxed(body);
-        let request: Request<BoxBody<Bytes, Error>> = self.builder.uri(&uri).header("Host", uri.host().unwrap()).body(boxed_body)?;
+        self.build(boxed_body)
+    }
+
+    /// Finish the builder and the create the request with a JSON body and a `content-type: application/json` header.
+    #[cfg(feature = "json")]
+    pub fn body_json<T: serde::Serialize>(mut self, value: &T) -> anyhow::Result<HttpRequest> {
+        let bytes = serde_json::to_vec(value)?;
+        self.builder = self.builder.header("content-type", "application/json");
+        self.body_bytes(bytes)
+    }
+
+    /// Finish the builder and the create the request with a `multipart/form-data` body and content-type.
+    pub fn body_multipart(mut self, form: MultipartForm) -> anyhow::Result<HttpRequest> {
+        self.builder = self.builder.header("content-type", form.content_type());
+        self.body_bytes(form.to_bytes())
+    }
+
+    fn build(self, body: BoxBody<Bytes, Error>) -> anyhow::Result<HttpRequest> {
+        let uri_string = self.uri_string.ok_or_else(|| anyhow::anyhow!("URI not set"))?;
+        let uri: Uri = uri_string.parse()?;
+        let host = uri.host().ok_or_else(|| anyhow::anyhow!("URI is missing a host: {}", uri_string))?.to_owned();
+        let request: Request<BoxBody<Bytes, Error>> = self.builder.uri(&uri).header("Host", host).body(body)?;
         Ok(HttpRequest::from(request))
     }
 
@@ -275,6 +406,14 @@ impl From<Request<BoxBody<Bytes, Error>>> for HttpRequest {
     }
 }
 
+impl From<Request<Bytes>> for HttpRequest {
+    fn from(req: Request<Bytes>) -> Self {
+        let (parts, body) = req.into_parts();
+        let body = Full::from(body).map_err(|e| match e {}).boxed();
+        HttpRequest::from_parts(body, parts)
+    }
+}
+
 impl From<Request<Incoming>> for HttpRequest {
     fn from(req: Request<Incoming>) -> Self {
         let (parts, body) = req.into_parts();
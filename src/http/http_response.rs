@@ -1,16 +1,25 @@
-use std::{marker::PhantomData, str::FromStr};
+use std::{convert::Infallible, io::SeekFrom, marker::PhantomData, path::Path, str::FromStr, time::{Duration, SystemTime, UNIX_EPOCH}};
 
 use anyhow::Error;
 use bytes::Bytes;
-use futures::StreamExt;
+use futures::{Stream, StreamExt};
 use http_body_util::{BodyExt, Empty, Full, StreamBody, combinators::BoxBody};
-use hyper::{HeaderMap, Response, body::{Frame, Incoming}, header::{HeaderName, HeaderValue}};
+use hyper::{HeaderMap, Response, Version, body::{Frame, Incoming}, header::{CONTENT_LENGTH, CONTENT_TYPE, HeaderName, HeaderValue}};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_util::io::ReaderStream;
 
-use crate::common::stream::ByteStream;
+use crate::{common::stream::ByteStream, http::{client::http_timing::TimingInfo, http_error::HttpError, http_sse::SseEvent}};
 
 pub struct Final;
 pub struct SetStatus;
 
+/// The outcome of parsing a `Range` header against the file size.
+enum ByteRange {
+    Ignored,
+    Unsatisfiable,
+    Satisfiable(u64, u64),
+}
+
 #[derive(Debug)]
 pub struct HttpResponse {
     body: BoxBody<Bytes, Error>,
@@ -35,6 +44,132 @@ impl HttpResponse {
         }
     }
 
+    /// Create a new `200` response sending each event from the stream as a Server-Sent Event as soon as it is produced.
+    /// 
+    /// Sets `content-type: text/event-stream` and `cache-control: no-cache`, the connection stays open until the stream ends.
+    pub fn sse<S>(stream: S) -> anyhow::Result<HttpResponse>
+    where
+        S: Stream<Item = SseEvent> + Send + Sync + 'static,
+    {
+        HttpResponse::builder()
+            .status(200)
+            .header("content-type", "text/event-stream")
+            .header("cache-control", "no-cache")
+            .body_chunks(stream.map(|event| Bytes::from(event.to_bytes())))
+    }
+
+    /// Create a new response streaming a file from disk, honoring an optional `Range` request header value.
+    /// 
+    /// Sets `content-type`, `content-length` and `accept-ranges`, and responds with:
+    /// - `200` with the full file when no range is given, or the range is ignored for being malformed, multiple ranges or not in bytes
+    /// - `206` with `content-range` for a satisfiable single range
+    /// - `404` when the file does not exist
+    /// - `416` with `content-range: bytes */size` when the range starts beyond the end of the file
+    pub async fn stream_file(path: impl AsRef<Path>, range: Option<&str>) -> anyhow::Result<HttpResponse> {
+        let path = path.as_ref();
+        let mut file = match tokio::fs::File::open(path).await {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return HttpResponse::builder().status(404).body_empty(),
+            Err(err) => return Err(err.into()),
+        };
+
+        let metadata = file.metadata().await?;
+        if !metadata.is_file() {
+            return HttpResponse::builder().status(404).body_empty();
+        }
+
+        let file_size = metadata.len();
+        let content_type = Self::content_type_from_path(path);
+
+        let (start, end) = match range.map(|range| Self::parse_range(range, file_size)).unwrap_or(ByteRange::Ignored) {
+            ByteRange::Ignored => {
+                let stream = ByteStream::new(ReaderStream::new(file));
+                return HttpResponse::builder()
+                    .status(200)
+                    .header("content-type", content_type)
+                    .header("content-length", file_size.to_string())
+                    .header("accept-ranges", "bytes")
+                    .body_stream(stream);
+            },
+            ByteRange::Unsatisfiable => {
+                return HttpResponse::builder()
+                    .status(416)
+                    .header("content-range", format!("bytes */{}", file_size))
+                    .body_empty();
+            },
+            ByteRange::Satisfiable(start, end) => (start, end),
+        };
+
+        let length = end - start + 1;
+        file.seek(SeekFrom::Start(start)).await?;
+        let stream = ByteStream::new(ReaderStream::new(file.take(length)));
+
+        HttpResponse::builder()
+            .status(206)
+            .header("content-type", content_type)
+            .header("content-length", length.to_string())
+            .header("content-range", format!("bytes {}-{}/{}", start, end, file_size))
+            .header("accept-ranges", "bytes")
+            .body_stream(stream)
+    }
+
+    /// Parses a single `bytes=` range into an inclusive `(start, end)` pair within the file size.
+    /// 
+    /// Ranges that cannot be parsed, multiple ranges and other units are ignored as allowed by RFC 9110.
+    fn parse_range(range: &str, file_size: u64) -> ByteRange {
+        let parse = |value: &str| value.bytes().all(|byte| byte.is_ascii_digit()).then(|| value.parse::<u64>().ok()).flatten();
+        let Some((start, end)) = range.trim().strip_prefix("bytes=").filter(|spec| !spec.contains(',')).and_then(|spec| spec.split_once('-')) else {
+            return ByteRange::Ignored;
+        };
+
+        let last = file_size.saturating_sub(1);
+        let (start, end) = match (start.trim(), end.trim()) {
+            ("", suffix) => match parse(suffix) {
+                Some(0) => return ByteRange::Unsatisfiable,
+                Some(suffix) => (file_size.saturating_sub(suffix), last),
+                None => return ByteRange::Ignored,
+            },
+            (start, "") => match parse(start) {
+                Some(start) => (start, last),
+                None => return ByteRange::Ignored,
+            },
+            (start, end) => match (parse(start), parse(end)) {
+                (Some(start), Some(end)) if start <= end => (start, end.min(last)),
+                _ => return ByteRange::Ignored,
+            },
+        };
+
+        if file_size == 0 || start >= file_size {
+            return ByteRange::Unsatisfiable;
+        }
+
+        ByteRange::Satisfiable(start, end)
+    }
+
+    fn content_type_from_path(path: &Path) -> &'static str {
+        let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default().to_ascii_lowercase();
+        match extension.as_str() {
+            "html" | "htm" => "text/html",
+            "css" => "text/css",
+            "js" | "mjs" => "text/javascript",
+            "json" => "application/json",
+            "txt" => "text/plain",
+            "csv" => "text/csv",
+            "xml" => "application/xml",
+            "pdf" => "application/pdf",
+            "png" => "image/png",
+            "jpg" | "jpeg" => "image/jpeg",
+            "gif" => "image/gif",
+            "svg" => "image/svg+xml",
+            "webp" => "image/webp",
+            "ico" => "image/x-icon",
+            "wasm" => "application/wasm",
+            "zip" => "application/zip",
+            "gz" => "application/gzip",
+            _ => "application/octet-stream",
+        }
+    }
+
     /// Returns the boxed body.
     /// 
     /// Used for moving body between requests/responses.
@@ -45,11 +180,89 @@ impl HttpResponse {
         ByteStream::new(stream)
     }
 
+    /// Collects the body and deserializes it from JSON.
+    ///
+    /// **This consumes the HttpResponse**
+    #[cfg(feature = "json")]
+    pub async fn json<T: serde::de::DeserializeOwned>(self) -> anyhow::Result<T> {
+        let body = self.body().to_bytes().await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    /// Collects the body and converts into a `http::Response<Bytes>` for interop with crates built on the `http` types.
+    ///
+    /// **This consumes the HttpResponse**
+    pub async fn into_http_response(self) -> anyhow::Result<Response<Bytes>> {
+        let body = self.body.collect().await?.to_bytes();
+        Ok(Response::from_parts(self.parts, body))
+    }
+
     /// Returns the status.
     pub fn status(&self) -> u16 {
         self.parts.status.as_u16()
     }
 
+    /// Returns the protocol version the response was received with, e.g. `HTTP/1.1` or `HTTP/2`.
+    pub fn version(&self) -> &'static str {
+        match self.parts.version {
+            Version::HTTP_09 => "HTTP/0.9",
+            Version::HTTP_10 => "HTTP/1.0",
+            Version::HTTP_2 => "HTTP/2",
+            Version::HTTP_3 => "HTTP/3",
+            _ => "HTTP/1.1",
+        }
+    }
+
+    /// Returns the response if the status is `2xx`, otherwise [`HttpError::Status`] with the status.
+    pub fn error_for_status(self) -> Result<Self, HttpError> {
+        match self.parts.status.is_success() {
+            true => Ok(self),
+            false => Err(HttpError::Status(self.status())),
+        }
+    }
+
+    /// Returns the delay requested by a `Retry-After` header, given either as seconds or as an HTTP-date.
+    /// 
+    /// Returns `None` when the header is absent or can not be parsed, and zero for dates in the past.
+    pub fn retry_after(&self) -> Option<Duration> {
+        let value = self.header("retry-after")?.to_str().ok()?.trim();
+        if let Ok(seconds) = value.parse::<u64>() {
+            return Some(Duration::from_secs(seconds));
+        }
+
+        let date = Self::parse_http_date(value)?;
+        Some(date.duration_since(SystemTime::now()).unwrap_or(Duration::ZERO))
+    }
+
+    /// Parses an IMF-fixdate such as `Sun, 06 Nov 1994 08:49:37 GMT`.
+    fn parse_http_date(value: &str) -> Option<SystemTime> {
+        let (_, date) = value.split_once(", ")?;
+        let mut parts = date.split_whitespace();
+        let day: i64 = parts.next()?.parse().ok()?;
+        let month = match parts.next()? {
+            "Jan" => 1, "Feb" => 2, "Mar" => 3, "Apr" => 4, "May" => 5, "Jun" => 6,
+            "Jul" => 7, "Aug" => 8, "Sep" => 9, "Oct" => 10, "Nov" => 11, "Dec" => 12,
+            _ => return None,
+        };
+        let year: i64 = parts.next()?.parse().ok()?;
+        let mut time = parts.next()?.split(':').map(|part| part.parse::<i64>());
+        let (hour, minute, second) = (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+        if parts.next()? != "GMT" || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+            return None;
+        }
+
+        // Days since the unix epoch from a civil date.
+        let y = if month <= 2 { year - 1 } else { year };
+        let era = y.div_euclid(400);
+        let year_of_era = y - era * 400;
+        let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = era * 146097 + day_of_era - 719468;
+
+        let seconds = days * 86400 + hour * 3600 + minute * 60 + second;
+        Some(UNIX_EPOCH + Duration::from_secs(u64::try_from(seconds).ok()?))
+    }
+
     /// Add a header.
     pub fn add_header(&mut self, key: impl AsRef<str>, value: impl AsRef<str>) -> anyhow::Result<()> {
         let key = HeaderName::from_str(key.as_ref())?;
@@ -72,6 +285,12 @@ impl HttpResponse {
     pub fn headers(&self) -> &HeaderMap {
         &self.parts.headers
     }
+
+    /// Returns the timing of the request, for responses received by a client created with
+    /// [`with_timing`](crate::http::client::http_client::HttpClient::with_timing).
+    pub fn timing(&self) -> Option<&TimingInfo> {
+        self.parts.extensions.get::<TimingInfo>()
+    }
 }
 
 pub struct HttpResponseBuilder<State> {
@@ -107,14 +326,60 @@ impl HttpResponseBuilder<Final> {
     }
 
     /// Finish the builder and the create the response with a body of bytes in memory.
-    pub fn body_bytes(self, body: impl Into<Bytes>) -> anyhow::Result<HttpResponse> {
-        let body = Full::from(body.into()).map_err(|e| match e {}).boxed();
+    /// 
+    /// The `content-length` header is set to the length of the body, replacing any previously set value.
+    pub fn body_bytes(mut self, body: impl Into<Bytes>) -> anyhow::Result<HttpResponse> {
+        let body = body.into();
+        if let Some(headers) = self.builder.headers_mut() {
+            headers.insert(CONTENT_LENGTH, HeaderValue::from(body.len()));
+        }
+
+        let body = Full::from(body).map_err(|e| match e {}).boxed();
         let response = self.builder.body(body)?;
         Ok(HttpResponse::from(response))
     }
 
+    /// Finish the builder and the create the response with a body of bytes and the `content-type` header.
+    pub fn body_with_type(mut self, body: impl Into<Bytes>, content_type: impl AsRef<str>) -> anyhow::Result<HttpResponse> {
+        let content_type = HeaderValue::from_str(content_type.as_ref())?;
+        if let Some(headers) = self.builder.headers_mut() {
+            headers.insert(CONTENT_TYPE, content_type);
+        }
+        self.body_bytes(body)
+    }
+
+    /// Finish the builder and the create the response with a text body and a `content-type: text/plain; charset=utf-8` header.
+    pub fn body_text(self, body: impl Into<String>) -> anyhow::Result<HttpResponse> {
+        self.body_with_type(body.into(), "text/plain; charset=utf-8")
+    }
+
+    /// Finish the builder and the create the response with an HTML body and a `content-type: text/html; charset=utf-8` header.
+    pub fn body_html(self, body: impl Into<String>) -> anyhow::Result<HttpResponse> {
+        self.body_with_type(body.into(), "text/html; charset=utf-8")
+    }
+
+    /// Finish the builder and the create the response with a JSON body and a `content-type: application/json` header.
+    #[cfg(feature = "json")]
+    pub fn body_json<T: serde::Serialize>(self, value: &T) -> anyhow::Result<HttpResponse> {
+        let bytes = serde_json::to_vec(value)?;
+        self.body_with_type(bytes, "application/json")
+    }
+
     /// Finish the builder and the create the response with a body of bytes as a stream.
-    pub fn body_stream(self, stream: ByteStream) -> anyhow::Result<HttpResponse> {
+    /// 
+    /// The `content-length` and `content-type` headers are set from the stream when known and not already set,
+    /// e.g. when streaming an object from S3 or a file from SFTP.
+    pub fn body_stream(mut self, stream: ByteStream) -> anyhow::Result<HttpResponse> {
+        let headers = self.builder.headers_ref();
+        let has_length = headers.is_some_and(|headers| headers.contains_key("content-length"));
+        let has_type = headers.is_some_and(|headers| headers.contains_key("content-type"));
+        if let Some(content_length) = stream.content_length() && !has_length {
+            self.builder = self.builder.header("content-length", content_length);
+        }
+        if let Some(content_type) = stream.content_type() && !has_type {
+            self.builder = self.builder.header("content-type", content_type);
+        }
+
         let mapped_stream = stream.inner_stream().map(|res| { res.map(Frame::data) });
         let body = StreamBody::new(mapped_stream);
         let boxed_body: BoxBody<Bytes, Error> = BodyExt::boxed(body);
@@ -122,6 +387,17 @@ impl HttpResponseBuilder<Final> {
         Ok(HttpResponse::from(response))
     }
 
+    /// Finish the builder and create the response with a body streamed from chunks as they are produced,
+    /// e.g. a large report or Server-Sent Events.
+    /// 
+    /// Without a `content-length` header the body is sent with `transfer-encoding: chunked` over HTTP/1.1.
+    pub fn body_chunks<S>(self, stream: S) -> anyhow::Result<HttpResponse>
+    where
+        S: Stream<Item = Bytes> + Send + Sync + 'static,
+    {
+        self.body_stream(ByteStream::new(stream.map(Ok::<Bytes, Infallible>)))
+    }
+
     /// Add a header to the response.
     pub fn header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
         self.builder = self.builder.header(key.into(), value.into());
@@ -150,6 +426,14 @@ impl From<Response<BoxBody<Bytes, Error>>> for HttpResponse {
     }
 }
 
+impl From<Response<Bytes>> for HttpResponse {
+    fn from(res: Response<Bytes>) -> Self {
+        let (parts, body) = res.into_parts();
+        let body = Full::from(body).map_err(|e| match e {}).boxed();
+        HttpResponse::from_parts(body, parts)
+    }
+}
+
 impl From<Response<Incoming>> for HttpResponse {
     fn from(req: Response<Incoming>) -> Self {
         let (parts, body) = req.into_parts();
@@ -0,0 +1,46 @@
+/// A Server-Sent Event sent by [`HttpResponse::sse`](crate::http::http_response::HttpResponse::sse).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    data: String,
+    event: Option<String>,
+    id: Option<String>,
+}
+
+impl SseEvent {
+    /// Creates an event with the data, multiple lines are sent as multiple `data` fields.
+    pub fn new(data: impl Into<String>) -> Self {
+        SseEvent {
+            data: data.into(),
+            event: None,
+            id: None,
+        }
+    }
+
+    /// Sets the event name, dispatched to listeners of that name instead of `message` in browsers.
+    pub fn event(mut self, event: impl Into<String>) -> Self {
+        self.event = Some(event.into());
+        self
+    }
+
+    /// Sets the event id, sent back by browsers in `Last-Event-ID` when reconnecting.
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Formats the event in the `text/event-stream` format, line breaks in the name and id are removed.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut output = String::new();
+        if let Some(event) = &self.event {
+            output.push_str(&format!("event: {}\n", event.replace(['\r', '\n'], "")));
+        }
+        if let Some(id) = &self.id {
+            output.push_str(&format!("id: {}\n", id.replace(['\r', '\n', '\0'], "")));
+        }
+        for line in self.data.split('\n') {
+            output.push_str(&format!("data: {}\n", line.strip_suffix('\r').unwrap_or(line)));
+        }
+        output.push('\n');
+        output.into_bytes()
+    }
+}
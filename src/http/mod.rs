@@ -12,6 +12,12 @@ pub mod server;
 pub mod http_request;
 #[cfg(feature = "http")]
 pub mod http_response;
+#[cfg(feature = "http")]
+pub mod http_multipart;
+#[cfg(feature = "http")]
+pub mod http_error;
+#[cfg(feature = "http")]
+pub mod http_sse;
 
 #[cfg(feature = "http")]
 #[cfg(test)]
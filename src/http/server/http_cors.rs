@@ -0,0 +1,121 @@
+use std::time::Duration;
+
+use hyper::{HeaderMap, header::{ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_MAX_AGE, ACCESS_CONTROL_REQUEST_HEADERS, HeaderValue, VARY}};
+
+use crate::http::http_response::HttpResponse;
+
+/// Cross-origin resource sharing settings for the [`HttpServer`](crate::http::server::http_server::HttpServer).
+#[derive(Debug, Clone)]
+pub struct HttpCorsConfig {
+    origins: Option<Vec<String>>,
+    methods: Vec<String>,
+    headers: Vec<String>,
+    credentials: bool,
+    max_age: Option<Duration>,
+}
+
+impl HttpCorsConfig {
+    /// Allows any origin with the methods `GET`, `HEAD`, `POST`, `PUT`, `PATCH` and `DELETE` and echoes requested headers.
+    pub fn new() -> Self {
+        HttpCorsConfig {
+            origins: None,
+            methods: ["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE"].iter().map(|method| method.to_string()).collect(),
+            headers: Vec::new(),
+            credentials: false,
+            max_age: None,
+        }
+    }
+
+    /// Adds an origin to the allowlist, e.g. `https://example.com`, only allowing the listed origins.
+    pub fn allow_origin(mut self, origin: impl AsRef<str>) -> Self {
+        self.origins.get_or_insert_with(Vec::new).push(origin.as_ref().trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Sets the methods allowed for cross-origin requests.
+    pub fn allow_methods(mut self, methods: &[&str]) -> Self {
+        self.methods = methods.iter().map(|method| method.to_ascii_uppercase()).collect();
+        self
+    }
+
+    /// Sets the request headers allowed for cross-origin requests, by default the headers requested by the preflight are allowed.
+    pub fn allow_headers(mut self, headers: &[&str]) -> Self {
+        self.headers = headers.iter().map(|header| header.to_ascii_lowercase()).collect();
+        self
+    }
+
+    /// Allows cookies and credentials on cross-origin requests, the allowed request origin is then echoed instead of `*`.
+    /// 
+    /// Credentials require an allowlist set with [`allow_origin`](Self::allow_origin), without one no origin is allowed.
+    pub fn allow_credentials(mut self, credentials: bool) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    /// Sets how long browsers may cache the preflight response.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Returns the `Access-Control-Allow-Origin` value for the origin, or `None` when the origin is not allowed.
+    fn allow_origin_value(&self, origin: &HeaderValue) -> Option<HeaderValue> {
+        match &self.origins {
+            Some(origins) => origin.to_str().ok().filter(|origin| origins.iter().any(|allowed| allowed == origin)).map(|_| origin.clone()),
+            None if self.credentials => None,
+            None => Some(HeaderValue::from_static("*")),
+        }
+    }
+
+    /// Answers a preflight request with `204` and the allowed methods and headers, or `403` when the origin is not allowed.
+    pub(crate) fn preflight(&self, origin: &HeaderValue, headers: &HeaderMap) -> HttpResponse {
+        let allow_origin = match self.allow_origin_value(origin) {
+            Some(value) => value,
+            None => return HttpResponse::builder().status(403).body_empty().unwrap(),
+        };
+
+        let allow_headers = match self.headers.is_empty() {
+            true => headers.get(ACCESS_CONTROL_REQUEST_HEADERS).and_then(|value| value.to_str().ok()).unwrap_or_default().to_string(),
+            false => self.headers.join(", "),
+        };
+
+        let mut builder = HttpResponse::builder().status(204)
+            .header(ACCESS_CONTROL_ALLOW_ORIGIN.as_str(), allow_origin.to_str().unwrap_or_default())
+            .header(ACCESS_CONTROL_ALLOW_METHODS.as_str(), self.methods.join(", "))
+            .header(VARY.as_str(), "origin, access-control-request-method, access-control-request-headers");
+
+        if !allow_headers.is_empty() {
+            builder = builder.header(ACCESS_CONTROL_ALLOW_HEADERS.as_str(), allow_headers);
+        }
+        if self.credentials {
+            builder = builder.header(ACCESS_CONTROL_ALLOW_CREDENTIALS.as_str(), "true");
+        }
+        if let Some(max_age) = self.max_age {
+            builder = builder.header(ACCESS_CONTROL_MAX_AGE.as_str(), max_age.as_secs().to_string());
+        }
+
+        builder.body_empty().unwrap()
+    }
+
+    /// Adds the CORS headers to a response for a request from the origin, if the origin is allowed.
+    pub(crate) fn apply(&self, origin: &HeaderValue, headers: &mut HeaderMap) {
+        let allow_origin = match self.allow_origin_value(origin) {
+            Some(value) => value,
+            None => return,
+        };
+
+        if allow_origin != "*" {
+            headers.append(VARY, HeaderValue::from_static("origin"));
+        }
+        headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, allow_origin);
+        if self.credentials {
+            headers.insert(ACCESS_CONTROL_ALLOW_CREDENTIALS, HeaderValue::from_static("true"));
+        }
+    }
+}
+
+impl Default for HttpCorsConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,142 @@
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use bytes::Bytes;
+use http_body_util::BodyExt;
+use hyper::{HeaderMap, Response, body::Body};
+use tokio::{sync::watch, time::Instant};
+
+use crate::http::{http_request::HttpRequest, http_response::HttpResponse, server::http_server::RouteCallback};
+
+/// Responses with larger bodies, or streamed bodies without an exact size, are not cached.
+const MAX_CACHED_BODY_SIZE: u64 = 1024 * 1024;
+
+struct CachedResponse {
+    created: Instant,
+    status: u16,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+pub(crate) struct IdempotencyCache {
+    header: String,
+    scope: Vec<String>,
+    ttl: Duration,
+    capacity: usize,
+    entries: Mutex<HashMap<String, CachedResponse>>,
+    in_flight: Mutex<HashMap<String, watch::Receiver<()>>>,
+}
+
+/// Marks a key as in flight until dropped, which wakes the duplicate requests waiting for its response.
+struct InFlight<'a> {
+    cache: &'a IdempotencyCache,
+    key: String,
+    _done: watch::Sender<()>,
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.cache.in_flight.lock().unwrap().remove(&self.key);
+    }
+}
+
+impl IdempotencyCache {
+    pub(crate) fn new(header: String, ttl: Duration, capacity: usize) -> Self {
+        IdempotencyCache {
+            header,
+            scope: Vec::new(),
+            ttl,
+            capacity,
+            entries: Mutex::new(HashMap::new()),
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Adds the value of a request header, such as `Authorization`, to the cache key.
+    pub(crate) fn scope(&mut self, header: String) {
+        self.scope.push(header);
+    }
+
+    /// Runs the route callback unless a cached response exists for the request idempotency key.
+    /// 
+    /// Duplicate requests arriving while the first is still being handled wait for its response.
+    pub(crate) async fn handle(&self, request: HttpRequest, callback: &RouteCallback) -> HttpResponse {
+        let mut key = match request.header(&self.header).and_then(|value| value.to_str().ok()) {
+            Some(value) => format!("{} {} {}", request.method(), request.path(), value),
+            None => return callback(request).await,
+        };
+        for header in &self.scope {
+            key.push('\n');
+            key.push_str(request.header(header).and_then(|value| value.to_str().ok()).unwrap_or_default());
+        }
+
+        let _in_flight = loop {
+            let mut done = {
+                let mut in_flight = self.in_flight.lock().unwrap();
+                if let Some(response) = self.get(&key) {
+                    tracing::trace!("Idempotency key {:?} replayed from cache", key);
+                    return response;
+                }
+
+                match in_flight.get(&key) {
+                    Some(done) => done.clone(),
+                    None => {
+                        let (done, receiver) = watch::channel(());
+                        in_flight.insert(key.clone(), receiver);
+                        break InFlight { cache: self, key: key.clone(), _done: done };
+                    },
+                }
+            };
+
+            tracing::trace!("Idempotency key {:?} waiting for the request in flight", key);
+            let _ = done.changed().await;
+        };
+
+        let response = Response::from(callback(request).await);
+        if response.status().is_server_error() || response.body().size_hint().exact().is_none_or(|size| size > MAX_CACHED_BODY_SIZE) {
+            return HttpResponse::from(response);
+        }
+
+        let (parts, body) = response.into_parts();
+        let body = match body.collect().await {
+            Ok(collected) => collected.to_bytes(),
+            Err(err) => {
+                tracing::error!("{:?}", err);
+                return HttpResponse::builder().status(500).body_empty().unwrap();
+            }
+        };
+
+        let cached = CachedResponse {
+            created: Instant::now(),
+            status: parts.status.as_u16(),
+            headers: parts.headers,
+            body,
+        };
+        let response = Self::to_response(&cached);
+        self.insert(key, cached);
+        response
+    }
+
+    fn get(&self, key: &str) -> Option<HttpResponse> {
+        let entries = self.entries.lock().unwrap();
+        entries.get(key).filter(|cached| cached.created.elapsed() < self.ttl).map(Self::to_response)
+    }
+
+    fn insert(&self, key: String, cached: CachedResponse) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, cached| cached.created.elapsed() < self.ttl);
+
+        while entries.len() >= self.capacity.max(1) {
+            let oldest = entries.iter().min_by_key(|(_, cached)| cached.created).map(|(key, _)| key.clone());
+            match oldest {
+                Some(oldest) => entries.remove(&oldest),
+                None => break,
+            };
+        }
+
+        entries.insert(key, cached);
+    }
+
+    fn to_response(cached: &CachedResponse) -> HttpResponse {
+        HttpResponse::builder().status(cached.status).headers(&cached.headers).body_bytes(cached.body.clone()).unwrap()
+    }
+}
@@ -1,26 +1,121 @@
-use std::{convert::Infallible, pin::Pin, sync::Arc};
+use std::{convert::Infallible, io::Write, net::SocketAddr, pin::Pin, sync::{Arc, atomic::{AtomicBool, Ordering}}, time::{Duration, Instant}};
 
-use futures::FutureExt;
-use http_body_util::{BodyExt, combinators::BoxBody};
-use hyper::{Request, Response, body::{Bytes, Incoming}, service::service_fn};
-use hyper_util::rt::TokioIo;
+use flate2::{Compression, write::{GzDecoder, GzEncoder, ZlibDecoder}};
+use futures::{FutureExt, TryStreamExt};
+use http_body_util::{BodyExt, Empty, Full, LengthLimitError, Limited, StreamBody, combinators::BoxBody};
+use hyper::{HeaderMap, Method, Request, Response, Uri, body::{Body, Bytes, Frame, Incoming}, header::{ACCEPT_ENCODING, ACCESS_CONTROL_REQUEST_METHOD, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, EXPECT, HeaderValue, ORIGIN, SERVER, VARY}, service::service_fn};
+use hyper_util::{rt::{TokioIo, TokioTimer}, server::graceful::{GracefulShutdown, Watcher}};
 use matchit::Router;
-use tokio::{net::{TcpListener, TcpStream}, signal::unix::{signal, SignalKind}};
+use tokio::{net::{TcpListener, TcpStream}, signal::unix::{signal, SignalKind}, sync::{Notify, OwnedSemaphorePermit, Semaphore, watch}};
 use tokio_rustls::TlsAcceptor;
 
-use crate::http::{executor::Executor, http_request::HttpRequest, http_response::HttpResponse, server::http_server_config::HttpServerConfig};
+use crate::http::{executor::Executor, http_request::HttpRequest, http_response::HttpResponse, server::{http_cors::HttpCorsConfig, http_idempotency_cache::IdempotencyCache, http_server_config::{Http2Config, HttpServerConfig}}};
 
-type RouteCallback = Arc<dyn Fn(HttpRequest) -> Pin<Box<dyn Future<Output = HttpResponse> + Send>> + Send + Sync>;
+pub(crate) type RouteCallback = Arc<dyn Fn(HttpRequest) -> Pin<Box<dyn Future<Output = HttpResponse> + Send>> + Send + Sync>;
 type BeforeCallback = Arc<dyn Fn(HttpRequest) -> Pin<Box<dyn Future<Output = BeforeResult> + Send>> + Send + Sync>;
 type AfterCallback = Arc<dyn Fn(HttpResponse) -> Pin<Box<dyn Future<Output = HttpResponse> + Send>> + Send + Sync>;
 type ErrorCallback = Arc<dyn Fn(String) -> Pin<Box<dyn Future<Output = HttpResponse> + Send>> + Send + Sync>;
+type RequestCallback = Arc<dyn Fn(&str, &Uri, &HeaderMap) + Send + Sync>;
+type ResponseCallback = Arc<dyn Fn(&str, &Uri, u16, Duration) + Send + Sync>;
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+type MiddlewareCallback = Arc<dyn Fn(HttpRequest, Next) -> Pin<Box<dyn Future<Output = HttpResponse> + Send>> + Send + Sync>;
+
+/// Bodies smaller than this are not worth compressing.
+const MIN_COMPRESS_SIZE: u64 = 1024;
+
+/// Bodies larger than this are sent as is rather than buffered in memory to be compressed.
+const MAX_COMPRESS_SIZE: u64 = 1024 * 1024;
+
+/// Compressed input is fed to the decoder in slices of this size so the decoded size is checked as it grows.
+const DECODE_SLICE_SIZE: usize = 1024;
+
+/// Streaming decoder for a supported request content-encoding.
+enum Decoder {
+    Gzip(GzDecoder<Vec<u8>>),
+    Deflate(ZlibDecoder<Vec<u8>>),
+}
+
+impl Decoder {
+    fn write_all(&mut self, data: &[u8]) -> std::io::Result<()> {
+        match self {
+            Decoder::Gzip(decoder) => decoder.write_all(data),
+            Decoder::Deflate(decoder) => decoder.write_all(data),
+        }
+    }
+
+    fn try_finish(&mut self) -> std::io::Result<()> {
+        match self {
+            Decoder::Gzip(decoder) => decoder.try_finish(),
+            Decoder::Deflate(decoder) => decoder.try_finish(),
+        }
+    }
+
+    /// Takes the output decoded so far.
+    fn take(&mut self) -> Vec<u8> {
+        match self {
+            Decoder::Gzip(decoder) => std::mem::take(decoder.get_mut()),
+            Decoder::Deflate(decoder) => std::mem::take(decoder.get_mut()),
+        }
+    }
+}
+
+/// Handlers registered for a single path, either for a specific method or as a catch-all.
+struct RouteEntry {
+    path: String,
+    methods: Vec<(Method, RouteCallback)>,
+    any: Option<RouteCallback>,
+}
+
+impl RouteEntry {
+    fn callback(&self, method: &Method) -> Option<&RouteCallback> {
+        let find = |method: &Method| self.methods.iter().find(|(m, _)| m == method).map(|(_, callback)| callback);
+        match *method {
+            Method::HEAD => find(method).or_else(|| find(&Method::GET)).or(self.any.as_ref()),
+            _ => find(method).or(self.any.as_ref()),
+        }
+    }
+
+    fn allow(&self) -> String {
+        self.methods.iter().map(|(method, _)| method.as_str()).collect::<Vec<&str>>().join(", ")
+    }
+}
 
 pub struct HttpServer {
     config: HttpServerConfig,
-    router: Router<RouteCallback>,
+    router: Router<usize>,
+    routes: Vec<RouteEntry>,
     before: Vec<BeforeCallback>,
+    middleware: Vec<MiddlewareCallback>,
     after: Vec<AfterCallback>,
     on_error: Option<ErrorCallback>,
+    on_request: Option<RequestCallback>,
+    on_response: Option<ResponseCallback>,
+    idempotency: Option<IdempotencyCache>,
+    cors: Option<HttpCorsConfig>,
+    shutdown: Arc<Notify>,
+    stopped: watch::Sender<bool>,
+}
+
+struct HttpServerContext {
+    server_header: Option<HeaderValue>,
+    suppress_server_header: bool,
+    max_body_size: Option<u64>,
+    compression: bool,
+    handler_timeout: Option<Duration>,
+    http2: Http2Config,
+    h2c: bool,
+    keep_alive: bool,
+    header_read_timeout: Option<Duration>,
+    router: Router<usize>,
+    routes: Vec<RouteEntry>,
+    before: Vec<BeforeCallback>,
+    middleware: Vec<MiddlewareCallback>,
+    after: Vec<AfterCallback>,
+    on_error: Option<ErrorCallback>,
+    on_request: Option<RequestCallback>,
+    on_response: Option<ResponseCallback>,
+    idempotency: Option<IdempotencyCache>,
+    cors: Option<HttpCorsConfig>,
 }
 
 impl HttpServer {
@@ -28,16 +123,32 @@ impl HttpServer {
         HttpServerBuilder {
             config,
             router: Router::new(),
+            routes: Vec::new(),
             before: Vec::new(),
+            middleware: Vec::new(),
             after: Vec::new(),
             on_error: None,
+            on_request: None,
+            on_response: None,
+            idempotency: None,
+            cors: None,
+            state: Arc::new(()),
+        }
+    }
+
+    /// Returns a handle that can be used to shut down the server without a system signal.
+    pub fn shutdown_handle(&self) -> HttpServerShutdown {
+        HttpServerShutdown {
+            shutdown: self.shutdown.clone(),
+            stopped: self.stopped.subscribe(),
         }
     }
 
     /// Run the HTTP server and begins listening for incoming TCP connections (optionally over TLS).
     ///
     /// This method binds to the configured host address and enters a loop to accept new TCP connections.
-    /// It also listens for system termination signals (SIGINT, SIGTERM) to gracefully shut down the server.
+    /// It also listens for system termination signals (SIGINT, SIGTERM) and the [`shutdown_handle`](HttpServer::shutdown_handle)
+    /// to gracefully shut down the server, waiting for open connections to finish their in-flight requests.
     pub async fn run(self) {
         let tls_acceptor = self.config.tls_config.map(|tls_config| {
             TlsAcceptor::from(Arc::new(tls_config))
@@ -47,11 +158,34 @@ impl HttpServer {
         let listener = TcpListener::bind(&host).await.expect("Failed to start TCP Listener");
         let mut sigterm = signal(SignalKind::terminate()).expect("Failed to start SIGTERM signal receiver");
         let mut sigint = signal(SignalKind::interrupt()).expect("Failed to start SIGINT signal receiver");
-        let router = Arc::new(self.router);
-        let before: Arc<[BeforeCallback]> = self.before.into();
-        let after: Arc<[AfterCallback]> = self.after.into();
-        let on_error = self.on_error;
+        let server_header = self.config.server_header.as_ref().map(|value| {
+            HeaderValue::from_str(value).expect("Invalid server header value")
+        });
+
+        let context = Arc::new(HttpServerContext {
+            server_header,
+            suppress_server_header: self.config.suppress_server_header,
+            max_body_size: self.config.max_body_size,
+            compression: self.config.compression,
+            handler_timeout: self.config.handler_timeout,
+            http2: self.config.http2.clone(),
+            h2c: self.config.h2c,
+            keep_alive: self.config.keep_alive,
+            header_read_timeout: self.config.header_read_timeout,
+            router: self.router,
+            routes: self.routes,
+            before: self.before,
+            middleware: self.middleware,
+            after: self.after,
+            on_error: self.on_error,
+            on_request: self.on_request,
+            on_response: self.on_response,
+            idempotency: self.idempotency,
+            cors: self.cors,
+        });
         
+        let semaphore = self.config.max_connections.map(|max_connections| Arc::new(Semaphore::new(max_connections)));
+        let graceful = GracefulShutdown::new();
         tracing::trace!("Started on {}", &host);
         loop {
             tokio::select! {
@@ -63,13 +197,15 @@ impl HttpServer {
                     drop(listener);
                     break;
                 },
-                result = listener.accept() => {
+                _ = self.shutdown.notified() => {
+                    drop(listener);
+                    break;
+                },
+                (permit, result) = Self::accept(&listener, semaphore.clone()) => {
                     let tls_acceptor = tls_acceptor.clone();
-                    let router = router.clone();
-                    let before = before.clone();
-                    let after = after.clone();
-                    let on_error = on_error.clone();
-                    let (tcp_stream, _client_addr) = match result {
+                    let context = context.clone();
+                    let watcher = graceful.watcher();
+                    let (tcp_stream, client_addr) = match result {
                         Ok(pair) => pair,
                         Err(err) => {
                             tracing::error!("{:?}", err);
@@ -79,34 +215,82 @@ impl HttpServer {
 
                     match tls_acceptor {
                         Some(acceptor) => {
-                            tokio::spawn(Self::tls_connection(acceptor, tcp_stream, router, before, after, on_error));
+                            tokio::spawn(async move {
+                                Self::tls_connection(acceptor, tcp_stream, client_addr, context, watcher).await;
+                                drop(permit);
+                            });
                         },
                         None => {
-                            tokio::spawn(Self::tcp_connection(tcp_stream, router, before, after, on_error));
+                            tokio::spawn(async move {
+                                Self::tcp_connection(tcp_stream, client_addr, context, watcher).await;
+                                drop(permit);
+                            });
                         },
                     }
                 }
             }
         }
 
+        graceful.shutdown().await;
+        self.stopped.send_replace(true);
         tracing::trace!("Shut down complete");
     }
 
-    async fn tcp_connection(tcp_stream: TcpStream, router: Arc<Router<RouteCallback>>, before: Arc<[BeforeCallback]>, after: Arc<[AfterCallback]>, on_error: Option<ErrorCallback>) {
-        let service = {
-            let router = router.clone();
-            service_fn(move |req| {
-                Self::incoming_request(req, router.clone(), before.clone(), after.clone(), on_error.clone())
-            })
+    /// Waits for a free connection slot when limited and then accepts the next connection.
+    async fn accept(listener: &TcpListener, semaphore: Option<Arc<Semaphore>>) -> (Option<OwnedSemaphorePermit>, std::io::Result<(TcpStream, SocketAddr)>) {
+        let permit = match semaphore {
+            Some(semaphore) => semaphore.acquire_owned().await.ok(),
+            None => None,
         };
+        (permit, listener.accept().await)
+    }
+
+    async fn tcp_connection(tcp_stream: TcpStream, client_addr: SocketAddr, context: Arc<HttpServerContext>, watcher: Watcher) {
+        let http1 = Self::http1_builder(&context);
+        let http2 = context.h2c.then(|| Self::http2_builder(&context.http2));
+        let service = service_fn(move |req| {
+            Self::incoming_request(req, client_addr, context.clone())
+        });
         
         let io = TokioIo::new(tcp_stream);
-        if let Err(err) = hyper::server::conn::http1::Builder::new().serve_connection(io, service).await {
+        let result = match http2 {
+            Some(http2) => watcher.watch(http2.serve_connection(io, service)).await,
+            None => watcher.watch(http1.serve_connection(io, service)).await,
+        };
+        if let Err(err) = result {
             tracing::error!("{:?}", err);
         }
     }
 
-    async fn tls_connection(tls_acceptor: TlsAcceptor, tcp_stream: TcpStream, router: Arc<Router<RouteCallback>>, before: Arc<[BeforeCallback]>, after: Arc<[AfterCallback]>, on_error: Option<ErrorCallback>) {
+    /// Creates an HTTP/1.1 connection builder with the keep-alive and header read timeout settings.
+    fn http1_builder(context: &HttpServerContext) -> hyper::server::conn::http1::Builder {
+        let mut builder = hyper::server::conn::http1::Builder::new();
+        builder.keep_alive(context.keep_alive);
+        if let Some(timeout) = context.header_read_timeout {
+            builder.timer(TokioTimer::new()).header_read_timeout(timeout);
+        }
+        builder
+    }
+
+    /// Creates an HTTP/2 connection builder, only overriding the hyper defaults for configured settings.
+    fn http2_builder(config: &Http2Config) -> hyper::server::conn::http2::Builder<Executor> {
+        let mut builder = hyper::server::conn::http2::Builder::new(Executor);
+        if let Some(max) = config.max_concurrent_streams {
+            builder.max_concurrent_streams(max);
+        }
+        if let Some(size) = config.initial_stream_window_size {
+            builder.initial_stream_window_size(size);
+        }
+        if let Some(size) = config.initial_connection_window_size {
+            builder.initial_connection_window_size(size);
+        }
+        if let Some(interval) = config.keep_alive_interval {
+            builder.timer(TokioTimer::new()).keep_alive_interval(interval);
+        }
+        builder
+    }
+
+    async fn tls_connection(tls_acceptor: TlsAcceptor, tcp_stream: TcpStream, client_addr: SocketAddr, context: Arc<HttpServerContext>, watcher: Watcher) {
         let tls_stream = match tls_acceptor.accept(tcp_stream).await {
             Ok(stream) => stream,
             Err(err) => {
@@ -115,31 +299,151 @@ impl HttpServer {
             },
         };
         
-        let service = {
-            let router = router.clone();
-            service_fn(move |req| {
-                Self::incoming_request(req, router.clone(), before.clone(), after.clone(), on_error.clone())
-            })
-        };
+        let http1 = Self::http1_builder(&context);
+        let http2 = Self::http2_builder(&context.http2);
+        let service = service_fn(move |req| {
+            Self::incoming_request(req, client_addr, context.clone())
+        });
         
         let io = TokioIo::new(tls_stream);
         let protocol = io.inner().get_ref().1.alpn_protocol();
         match protocol {
             Some(b"h2") => {
-                if let Err(err) = hyper::server::conn::http2::Builder::new(Executor).serve_connection(io, service).await {
+                if let Err(err) = watcher.watch(http2.serve_connection(io, service)).await {
                     tracing::error!("TLS handshake failed {:?}", err);
                 }
             }
             _ => {
-                if let Err(err) = hyper::server::conn::http1::Builder::new().serve_connection(io, service).await {
+                if let Err(err) = watcher.watch(http1.serve_connection(io, service)).await {
                     tracing::error!("{:?}", err);
                 }
             }
         }
     }
 
-    async fn incoming_request(request: Request<Incoming>, router: Arc<Router<RouteCallback>>, before: Arc<[BeforeCallback]>, after: Arc<[AfterCallback]>, on_error: Option<ErrorCallback>) -> Result<Response<BoxBody<Bytes, anyhow::Error>>, Infallible> {
-        let result = std::panic::AssertUnwindSafe(Self::inner_request(request, router, before, after)).catch_unwind().await;
+    async fn incoming_request(request: Request<Incoming>, client_addr: SocketAddr, context: Arc<HttpServerContext>) -> Result<Response<BoxBody<Bytes, anyhow::Error>>, Infallible> {
+        let start = Instant::now();
+        let method = request.method().clone();
+        let uri = request.uri().clone();
+        if let Some(callback) = &context.on_request {
+            callback(method.as_str(), &uri, request.headers());
+        }
+
+        let compress = context.compression && request.method() != Method::HEAD && Self::accepts_gzip(request.headers());
+        let cors = context.cors.as_ref().zip(request.headers().get(ORIGIN).cloned());
+        let preflight = request.method() == Method::OPTIONS && request.headers().contains_key(ACCESS_CONTROL_REQUEST_METHOD);
+
+        let mut response = match &cors {
+            Some((cors, origin)) if preflight => Response::from(cors.preflight(origin, request.headers())),
+            _ => Self::dispatch_request(request, client_addr, context.clone()).await?,
+        };
+
+        if compress {
+            response = Self::compress_response(response).await;
+        }
+
+        if let Some((cors, origin)) = &cors && !preflight {
+            cors.apply(origin, response.headers_mut());
+        }
+
+        if let Some(value) = &context.server_header {
+            response.headers_mut().insert(SERVER, value.clone());
+        } else if context.suppress_server_header {
+            response.headers_mut().remove(SERVER);
+        }
+
+        if method == Method::HEAD {
+            response = Self::strip_body(response);
+        }
+
+        if let Some(callback) = &context.on_response {
+            callback(method.as_str(), &uri, response.status().as_u16(), start.elapsed());
+        }
+
+        Ok(response)
+    }
+
+    /// Replaces the body of a response to a `HEAD` request with an empty body, keeping the `content-length` of the original body.
+    fn strip_body(response: Response<BoxBody<Bytes, anyhow::Error>>) -> Response<BoxBody<Bytes, anyhow::Error>> {
+        let (mut parts, body) = response.into_parts();
+        if let Some(length) = body.size_hint().exact() && !parts.headers.contains_key(CONTENT_LENGTH) {
+            parts.headers.insert(CONTENT_LENGTH, HeaderValue::from(length));
+        }
+        Response::from_parts(parts, Empty::new().map_err(|e| match e {}).boxed())
+    }
+
+    fn accepts_gzip(headers: &HeaderMap) -> bool {
+        headers.get_all(ACCEPT_ENCODING).iter().filter_map(|value| value.to_str().ok()).flat_map(|value| value.split(',')).any(|encoding| {
+            let mut params = encoding.split(';').map(str::trim);
+            let coding = params.next().unwrap_or_default();
+            let rejected = params.any(|param| param.strip_prefix("q=").and_then(|q| q.parse::<f32>().ok()) == Some(0.0));
+            (coding.eq_ignore_ascii_case("gzip") || coding == "*") && !rejected
+        })
+    }
+
+    /// Compresses the response body with gzip unless it is streamed, outside the compressible size range, already encoded or of a compressed content type.
+    /// 
+    /// Only bodies held in memory with an exact size are compressed, so streams are never buffered. Skipped responses are left unchanged.
+    async fn compress_response(response: Response<BoxBody<Bytes, anyhow::Error>>) -> Response<BoxBody<Bytes, anyhow::Error>> {
+        let status = response.status().as_u16();
+        let headers = response.headers();
+        let content_type = headers.get(CONTENT_TYPE).and_then(|value| value.to_str().ok()).unwrap_or_default().to_ascii_lowercase();
+        let compressed_type = (content_type.starts_with("image/") && !content_type.starts_with("image/svg"))
+            || content_type.starts_with("video/")
+            || content_type.starts_with("audio/")
+            || content_type.starts_with("font/woff")
+            || ["application/zip", "application/gzip", "application/x-gzip", "application/zstd", "application/x-7z-compressed"].iter().any(|value| content_type.starts_with(value));
+        let length = response.body().size_hint().exact();
+
+        if !(200..300).contains(&status) || status == 204 || status == 206 || headers.contains_key(CONTENT_ENCODING) || compressed_type || length.is_none_or(|length| !(MIN_COMPRESS_SIZE..=MAX_COMPRESS_SIZE).contains(&length)) {
+            return response;
+        }
+
+        let (mut parts, body) = response.into_parts();
+        let bytes = match body.collect().await {
+            Ok(collected) => collected.to_bytes(),
+            Err(err) => {
+                tracing::error!("{:?}", err);
+                return Response::from(HttpResponse::builder().status(500).body_empty().unwrap());
+            },
+        };
+
+        parts.headers.append(VARY, HeaderValue::from_static("accept-encoding"));
+        let input = bytes.clone();
+        let compressed = tokio::task::spawn_blocking(move || {
+            let mut encoder = GzEncoder::new(Vec::with_capacity(input.len() / 2), Compression::default());
+            encoder.write_all(&input)?;
+            encoder.finish()
+        }).await.map_err(anyhow::Error::from).and_then(|result| result.map_err(anyhow::Error::from));
+        let compressed = match compressed {
+            Ok(compressed) => compressed,
+            Err(err) => {
+                tracing::error!("{:?}", err);
+                return Response::from_parts(parts, Full::from(bytes).map_err(|e| match e {}).boxed());
+            },
+        };
+        if compressed.len() >= bytes.len() {
+            return Response::from_parts(parts, Full::from(bytes).map_err(|e| match e {}).boxed());
+        }
+
+        parts.headers.insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+        parts.headers.insert(CONTENT_LENGTH, HeaderValue::from(compressed.len()));
+        Response::from_parts(parts, Full::from(compressed).map_err(|e| match e {}).boxed())
+    }
+
+    async fn dispatch_request(request: Request<Incoming>, client_addr: SocketAddr, context: Arc<HttpServerContext>) -> Result<Response<BoxBody<Bytes, anyhow::Error>>, Infallible> {
+        let future = std::panic::AssertUnwindSafe(Self::inner_request(request, client_addr, context.clone())).catch_unwind();
+        let result = match context.handler_timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, future).await {
+                Ok(result) => result,
+                Err(_) => {
+                    tracing::warn!("Request handler timed out after {:?}", timeout);
+                    return Ok(Response::from(HttpResponse::builder().status(504).body_empty().unwrap()));
+                },
+            },
+            None => future.await,
+        };
+
         match result {
             Ok(response) => response,
             Err(err) => {
@@ -151,7 +455,7 @@ impl HttpServer {
                     "Unknown panic!"
                 };
 
-                let response = match on_error {
+                let response = match &context.on_error {
                     Some(handler) => {
                         handler(error.to_string()).await
                     },
@@ -166,25 +470,121 @@ impl HttpServer {
         }
     }
 
-    async fn inner_request(request: Request<Incoming>, router: Arc<Router<RouteCallback>>, before: Arc<[BeforeCallback]>, after: Arc<[AfterCallback]>) -> Result<Response<BoxBody<Bytes, anyhow::Error>>, Infallible> {
-        let (parts, body) = request.into_parts();
+    /// Decompresses the request body as it is read, one slice of compressed input at a time.
+    fn decode_body(body: BoxBody<Bytes, BoxError>, decoder: Decoder) -> BoxBody<Bytes, BoxError> {
+        let stream = futures::stream::try_unfold((body.into_data_stream(), Bytes::new(), Some(decoder)), |(mut data, mut pending, decoder)| async move {
+            let Some(mut decoder) = decoder else {
+                return Ok(None);
+            };
+
+            if pending.is_empty() {
+                match data.try_next().await? {
+                    Some(chunk) => pending = chunk,
+                    None => {
+                        decoder.try_finish()?;
+                        return Ok(Some((Frame::data(Bytes::from(decoder.take())), (data, pending, None))));
+                    },
+                }
+            }
+
+            let slice = pending.split_to(pending.len().min(DECODE_SLICE_SIZE));
+            decoder.write_all(&slice)?;
+            Ok(Some((Frame::data(Bytes::from(decoder.take())), (data, pending, Some(decoder)))))
+        });
+        StreamBody::new(stream).boxed()
+    }
+
+    /// Limits the body to the max body size, setting `exceeded` when the limit is hit so the server can answer `413`.
+    fn limit_body(body: BoxBody<Bytes, BoxError>, max_body_size: Option<u64>, exceeded: &Arc<AtomicBool>) -> BoxBody<Bytes, BoxError> {
+        let Some(max_body_size) = max_body_size else {
+            return body;
+        };
+
+        let exceeded = exceeded.clone();
+        Limited::new(body, max_body_size as usize).map_err(move |err| {
+            if err.is::<LengthLimitError>() {
+                exceeded.store(true, Ordering::Relaxed);
+            }
+            err
+        }).boxed()
+    }
+
+    /// Replaces the response with `413` when the request body exceeded the max body size while it was read.
+    fn exceeded_response(response: HttpResponse, exceeded: &AtomicBool) -> HttpResponse {
+        match exceeded.load(Ordering::Relaxed) {
+            true => HttpResponse::builder().status(413).body_empty().unwrap(),
+            false => response,
+        }
+    }
+
+    async fn inner_request(request: Request<Incoming>, client_addr: SocketAddr, context: Arc<HttpServerContext>) -> Result<Response<BoxBody<Bytes, anyhow::Error>>, Infallible> {
+        let (mut parts, body) = request.into_parts();
         let path = parts.uri.path().to_owned();
-        match router.at(&path) {
+        match context.router.at(&path) {
             Ok(matched) => {
+                let route = &context.routes[*matched.value];
+                let callback = match route.callback(&parts.method) {
+                    Some(callback) => callback,
+                    None => {
+                        let response = HttpResponse::builder().status(405).header("allow", route.allow()).body_empty().unwrap();
+                        return Ok(Response::from(response))
+                    },
+                };
+
+                // Rejecting before the body is polled means hyper never sends `100 Continue` to clients expecting it.
+                if let Some(expect) = parts.headers.get(EXPECT) && !expect.as_bytes().eq_ignore_ascii_case(b"100-continue") {
+                    let response = HttpResponse::builder().status(417).body_empty().unwrap();
+                    return Ok(Response::from(response))
+                }
+
+                if let Some(max_body_size) = context.max_body_size {
+                    let content_length = parts.headers.get(CONTENT_LENGTH).and_then(|value| value.to_str().ok()).and_then(|value| value.parse::<u64>().ok());
+                    if content_length.is_some_and(|length| length > max_body_size) {
+                        let response = HttpResponse::builder().status(413).body_empty().unwrap();
+                        return Ok(Response::from(response))
+                    }
+                }
+
+                let decoder = match parts.headers.get(CONTENT_ENCODING).filter(|_| context.compression) {
+                    Some(value) => match value.to_str().unwrap_or_default().trim().to_ascii_lowercase().as_str() {
+                        "identity" => None,
+                        "gzip" | "x-gzip" => Some(Decoder::Gzip(GzDecoder::new(Vec::new()))),
+                        "deflate" => Some(Decoder::Deflate(ZlibDecoder::new(Vec::new()))),
+                        _ => {
+                            let response = HttpResponse::builder().status(415).header("accept-encoding", "gzip, deflate").body_empty().unwrap();
+                            return Ok(Response::from(response))
+                        },
+                    },
+                    None => None,
+                };
+
                 let params: Vec<(String, String)> = matched.params.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
 
-                let body = body.map_err(anyhow::Error::from);
-                let mut req = HttpRequest::from_parts_with_params(body.boxed(), parts, params);
+                let exceeded = Arc::new(AtomicBool::new(false));
+                let body = Self::limit_body(body.map_err(BoxError::from).boxed(), context.max_body_size, &exceeded);
+                let body = match decoder {
+                    Some(decoder) => {
+                        parts.headers.remove(CONTENT_ENCODING);
+                        parts.headers.remove(CONTENT_LENGTH);
+                        Self::limit_body(Self::decode_body(body, decoder), context.max_body_size, &exceeded)
+                    },
+                    None => body,
+                };
+                let body = body.map_err(|err| match err.downcast::<LengthLimitError>() {
+                    Ok(err) => anyhow::Error::new(*err),
+                    Err(err) => anyhow::Error::from_boxed(err),
+                }).boxed();
+                let mut req = HttpRequest::from_parts_with_params(body, parts, params).with_remote_addr(client_addr);
 
-                for handler in before.iter() {
+                for handler in context.before.iter() {
                     match handler(req).await {
                         BeforeResult::Next(request) => {
                             req = request;
                         },
                         BeforeResult::Response(response) => {
-                            let mut response = response;
+                            let mut response = Self::exceeded_response(response, &exceeded);
 
-                            for handler in after.iter() {
+                            for handler in context.after.iter() {
                                 response = handler(response).await;
                             }
 
@@ -193,10 +593,10 @@ impl HttpServer {
                     }
                 }
 
-                let callback = matched.value;
-                let mut response = callback(req).await;
+                let next = Next { context: context.clone(), callback: callback.clone(), index: 0 };
+                let mut response = Self::exceeded_response(next.run(req).await, &exceeded);
 
-                for handler in after.iter() {
+                for handler in context.after.iter() {
                     response = handler(response).await;
                 }
 
@@ -210,6 +610,47 @@ impl HttpServer {
     }
 }
 
+/// The rest of the middleware chain followed by the matched route handler.
+pub struct Next {
+    context: Arc<HttpServerContext>,
+    callback: RouteCallback,
+    index: usize,
+}
+
+impl Next {
+    /// Passes the request to the next middleware, or the route handler after the last middleware.
+    pub async fn run(self, request: HttpRequest) -> HttpResponse {
+        match self.context.middleware.get(self.index).cloned() {
+            Some(middleware) => {
+                let next = Next { context: self.context.clone(), callback: self.callback.clone(), index: self.index + 1 };
+                middleware(request, next).await
+            },
+            None => match &self.context.idempotency {
+                Some(cache) => cache.handle(request, &self.callback).await,
+                None => (self.callback)(request).await,
+            },
+        }
+    }
+}
+
+/// Cloneable handle for shutting down a running [`HttpServer`].
+#[derive(Clone)]
+pub struct HttpServerShutdown {
+    shutdown: Arc<Notify>,
+    stopped: watch::Receiver<bool>,
+}
+
+impl HttpServerShutdown {
+    /// Signals the server to stop accepting connections and waits until open connections are closed.
+    /// 
+    /// Returns immediately if the server has already stopped or was dropped without running.
+    pub async fn shutdown(&self) {
+        self.shutdown.notify_one();
+        let mut stopped = self.stopped.clone();
+        let _ = stopped.wait_for(|stopped| *stopped).await;
+    }
+}
+
 /// Result type used internally by the request middleware pipeline.
 ///
 /// This type is typically not used directly by end users.
@@ -236,15 +677,42 @@ impl From<HttpResponse> for BeforeResult {
     }
 }
 
-pub struct HttpServerBuilder {
+pub struct HttpServerBuilder<S = ()> {
     config: HttpServerConfig,
-    router: Router<RouteCallback>,
+    router: Router<usize>,
+    routes: Vec<RouteEntry>,
     before: Vec<BeforeCallback>,
+    middleware: Vec<MiddlewareCallback>,
     after: Vec<AfterCallback>,
     on_error: Option<ErrorCallback>,
+    on_request: Option<RequestCallback>,
+    on_response: Option<ResponseCallback>,
+    idempotency: Option<IdempotencyCache>,
+    cors: Option<HttpCorsConfig>,
+    state: Arc<S>,
 }
 
-impl HttpServerBuilder {
+impl<S: Send + Sync + 'static> HttpServerBuilder<S> {
+    /// Sets shared state, such as a database pool or config, passed to routes registered with [`route_with_state`](HttpServerBuilder::route_with_state).
+    /// 
+    /// Routes capture the state when registered, so the state should be set before registering them.
+    pub fn with_state<T: Send + Sync + 'static>(self, state: T) -> HttpServerBuilder<T> {
+        HttpServerBuilder {
+            config: self.config,
+            router: self.router,
+            routes: self.routes,
+            before: self.before,
+            middleware: self.middleware,
+            after: self.after,
+            on_error: self.on_error,
+            on_request: self.on_request,
+            on_response: self.on_response,
+            idempotency: self.idempotency,
+            cors: self.cors,
+            state: Arc::new(state),
+        }
+    }
+
     /// Add a middleware to the request pipeline.
     ///
     /// - Return `HttpRequest` to continue to the next middleware or route handler
@@ -267,13 +735,118 @@ impl HttpServerBuilder {
         self
     }
 
+    /// Add a middleware wrapping the matched route handler, running in registration order after the `before` pipeline.
+    /// 
+    /// The middleware either returns a response to short-circuit, e.g. `401` for unauthenticated requests, or calls
+    /// [`Next::run`] to continue to the next middleware and the route handler, and can then modify the response.
+    /// 
+    /// Responses returned without reading the body are sent instead of `100 Continue` to clients sending `Expect: 100-continue`.
+    pub fn middleware<T, Fut>(mut self, callback: T) -> Self
+    where
+        T: Fn(HttpRequest, Next) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = HttpResponse> + Send + 'static,
+    {
+        self.middleware.push(Arc::new(move |request, next| Box::pin(callback(request, next))));
+        self
+    }
+
     /// Registers a route with a path, associating it with a handler callback.
+    /// 
+    /// Paths can contain named params such as `/users/{id}` and a trailing catch-all such as `/files/{*path}`,
+    /// available with [`HttpRequest::param`].
+    /// 
+    /// The handler receives every method not registered with [`route_method`](HttpServerBuilder::route_method) for the same path.
     pub fn route<T, Fut>(mut self, path: impl Into<String>, callback: T) -> Self
     where
         T: Fn(HttpRequest) -> Fut + Send + Sync + 'static,
         Fut: Future<Output = HttpResponse> + Send + 'static,
     {
-        self.router.insert(path.into(), Arc::new(move |request| Box::pin(callback(request)))).unwrap();
+        self.insert_route(path.into(), None, Arc::new(move |request| Box::pin(callback(request))));
+        self
+    }
+
+    /// Registers a route with a path, associating it with a handler callback receiving the state set with [`with_state`](HttpServerBuilder::with_state).
+    pub fn route_with_state<T, Fut>(mut self, path: impl Into<String>, callback: T) -> Self
+    where
+        T: Fn(Arc<S>, HttpRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = HttpResponse> + Send + 'static,
+    {
+        let state = self.state.clone();
+        self.insert_route(path.into(), None, Arc::new(move |request| Box::pin(callback(state.clone(), request))));
+        self
+    }
+
+    /// Registers a route with a method and path, associating it with a handler callback.
+    /// 
+    /// Requests to a path with only method routes and no matching method are answered with `405` and an `Allow` header.
+    /// 
+    /// The method is case-sensitive, e.g. `GET` or `PROPFIND`, and fails if it is not a valid method token.
+    pub fn route_method<T, Fut>(mut self, method: impl AsRef<str>, path: impl Into<String>, callback: T) -> anyhow::Result<Self>
+    where
+        T: Fn(HttpRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = HttpResponse> + Send + 'static,
+    {
+        let method = Method::from_bytes(method.as_ref().as_bytes())?;
+        self.insert_route(path.into(), Some(method), Arc::new(move |request| Box::pin(callback(request))));
+        Ok(self)
+    }
+
+    fn insert_route(&mut self, path: String, method: Option<Method>, callback: RouteCallback) {
+        let index = match self.routes.iter().position(|route| route.path == path) {
+            Some(index) => index,
+            None => {
+                self.router.insert(path.clone(), self.routes.len()).unwrap();
+                self.routes.push(RouteEntry { path: path.clone(), methods: Vec::new(), any: None });
+                self.routes.len() - 1
+            },
+        };
+
+        let route = &mut self.routes[index];
+        let exists = match method {
+            Some(method) => {
+                let exists = route.methods.iter().any(|(m, _)| *m == method);
+                route.methods.push((method, callback));
+                exists
+            },
+            None => route.any.replace(callback).is_some(),
+        };
+
+        if exists {
+            panic!("Route already registered for {}", path);
+        }
+    }
+
+    /// Registers a route accepting a JSON body of at most `max_body_size` bytes.
+    /// 
+    /// The body is buffered and validated before the handler runs, responding with `413` when it is too large
+    /// and `400` when it is not valid JSON, so the handler can assume well-formed, size-bounded input.
+    #[cfg(feature = "json")]
+    pub fn route_json<T, Fut>(mut self, path: impl Into<String>, max_body_size: usize, callback: T) -> Self
+    where
+        T: Fn(HttpRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = HttpResponse> + Send + 'static,
+    {
+        let callback = Arc::new(callback);
+        self.insert_route(path.into(), None, Arc::new(move |request: HttpRequest| {
+            let callback = Arc::clone(&callback);
+            Box::pin(async move {
+                let request = match request.buffer_body(max_body_size).await {
+                    Ok((request, bytes)) => {
+                        if serde_json::from_slice::<serde_json::Value>(&bytes).is_err() {
+                            return HttpResponse::builder().status(400).body_empty().unwrap();
+                        }
+                        request
+                    },
+                    Err(crate::http::http_request::BodyError::TooLarge) => return HttpResponse::builder().status(413).body_empty().unwrap(),
+                    Err(crate::http::http_request::BodyError::Other(err)) => {
+                        tracing::error!("{:?}", err);
+                        return HttpResponse::builder().status(400).body_empty().unwrap();
+                    },
+                };
+
+                callback(request).await
+            })
+        }));
         self
     }
 
@@ -305,13 +878,74 @@ impl HttpServerBuilder {
         self
     }
 
+    /// Registers a hook invoked for every incoming request with the method, uri and headers, before it is handled.
+    /// 
+    /// Useful for access logs and metrics, registering multiple hooks will overwrite the previous one.
+    pub fn on_request<T>(mut self, callback: T) -> Self
+    where 
+        T: Fn(&str, &Uri, &HeaderMap) + Send + Sync + 'static,
+    {
+        self.on_request = Some(Arc::new(callback));
+        self
+    }
+
+    /// Registers a hook invoked for every response with the request method and uri, the response status and the time
+    /// taken to produce the response, not including sending the body.
+    /// 
+    /// Useful for access logs and metrics, registering multiple hooks will overwrite the previous one.
+    pub fn on_response<T>(mut self, callback: T) -> Self
+    where 
+        T: Fn(&str, &Uri, u16, Duration) + Send + Sync + 'static,
+    {
+        self.on_response = Some(Arc::new(callback));
+        self
+    }
+
+    /// Enables deduplication of requests carrying an idempotency key header, such as `Idempotency-Key`.
+    /// 
+    /// The first response for a key is cached for `ttl` and returned for duplicate requests to the same method and path
+    /// instead of running the route handler again. Requests without the header bypass deduplication.
+    /// 
+    /// Duplicates arriving while the first request is still being handled wait for its response.
+    /// 
+    /// At most `capacity` responses are cached, evicting expired and then the oldest entries. Server errors (`5xx`) and
+    /// streamed bodies or bodies larger than 1 MiB are not cached, duplicates of those run the route handler again.
+    pub fn idempotency(mut self, header: impl Into<String>, ttl: Duration, capacity: usize) -> Self {
+        self.idempotency = Some(IdempotencyCache::new(header.into(), ttl, capacity));
+        self
+    }
+
+    /// Adds the value of a request header to the idempotency key, such as `Authorization` so that clients
+    /// reusing the same key never receive each other's responses. Must be called after [`idempotency`](HttpServerBuilder::idempotency).
+    pub fn idempotency_scope(mut self, header: impl Into<String>) -> Self {
+        self.idempotency.as_mut().expect("Idempotency must be enabled before adding a scope.").scope(header.into());
+        self
+    }
+
+    /// Enables cross-origin resource sharing for browser clients.
+    /// 
+    /// Preflight `OPTIONS` requests are answered automatically with the configured `Access-Control-Allow-*` headers
+    /// and responses to allowed origins get an `Access-Control-Allow-Origin` header.
+    pub fn cors(mut self, config: HttpCorsConfig) -> Self {
+        self.cors = Some(config);
+        self
+    }
+
     pub fn build(self) -> HttpServer {
         HttpServer {
             config: self.config,
             router: self.router,
+            routes: self.routes,
             before: self.before,
+            middleware: self.middleware,
             on_error: self.on_error,
-            after: self.after
+            on_request: self.on_request,
+            on_response: self.on_response,
+            after: self.after,
+            idempotency: self.idempotency,
+            cors: self.cors,
+            shutdown: Arc::new(Notify::new()),
+            stopped: watch::channel(false).0,
         }
     }
 }
\ No newline at end of file
@@ -1,13 +1,26 @@
-use std::path::Path;
+use std::{path::Path, time::Duration};
 
 use rustls::{ServerConfig};
 
 use crate::http::crypto::Crypto;
 
+/// Default max size of request bodies, 16 MiB.
+pub const DEFAULT_MAX_BODY_SIZE: u64 = 16 * 1024 * 1024;
+
 pub struct HttpServerConfig {
     pub ip: String,
     pub port: u16,
     pub tls_config: Option<ServerConfig>,
+    pub server_header: Option<String>,
+    pub suppress_server_header: bool,
+    pub max_body_size: Option<u64>,
+    pub compression: bool,
+    pub max_connections: Option<usize>,
+    pub handler_timeout: Option<Duration>,
+    pub http2: Http2Config,
+    pub h2c: bool,
+    pub keep_alive: bool,
+    pub header_read_timeout: Option<Duration>,
 }
 
 impl HttpServerConfig {
@@ -16,9 +29,99 @@ impl HttpServerConfig {
             ip: ip.into(),
             port,
             tls_config: None,
+            server_header: None,
+            suppress_server_header: false,
+            max_body_size: Some(DEFAULT_MAX_BODY_SIZE),
+            compression: false,
+            max_connections: None,
+            handler_timeout: None,
+            http2: Http2Config::default(),
+            h2c: false,
+            keep_alive: true,
+            header_read_timeout: None,
         }
     }
 
+    /// Sets the `Server` header on every response, or removes it from every response when `None`.
+    /// 
+    /// By default responses are left untouched and no `Server` header is added.
+    pub fn server_header(mut self, value: Option<String>) -> Self {
+        self.suppress_server_header = value.is_none();
+        self.server_header = value;
+        self
+    }
+
+    /// Sets the max size of request bodies in bytes, or `None` to allow bodies of any size.
+    /// 
+    /// Requests with a larger `content-length` are rejected with `413` before the body is read, which also applies to
+    /// clients waiting on `Expect: 100-continue`. Bodies without a `content-length` fail to read past the limit.
+    /// 
+    /// Defaults to [`DEFAULT_MAX_BODY_SIZE`] (16 MiB).
+    pub fn max_body_size(mut self, max_body_size: Option<u64>) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    /// Compresses responses with gzip for clients sending `Accept-Encoding: gzip`.
+    /// 
+    /// Only bodies held in memory of between 1 KiB and 1 MiB are compressed. Streamed bodies, even with a known length, and
+    /// already compressed content types such as images, video, audio and archives are sent as is.
+    /// 
+    /// Request bodies with `Content-Encoding: gzip` or `deflate` are decompressed before reaching the handler, with the
+    /// decompressed size limited by the max body size. Other encodings are rejected with `415`. Disabled by default.
+    pub fn compression(mut self, compression: bool) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Sets the max number of connections served at the same time, or `None` for no limit.
+    /// 
+    /// Once the limit is reached new connections are not accepted and wait in the listen backlog until an open
+    /// connection is closed. No limit by default.
+    pub fn max_connections(mut self, max_connections: Option<usize>) -> Self {
+        self.max_connections = max_connections;
+        self
+    }
+
+    /// Sets the max time a request may take to be handled, including middleware, before `504` is returned.
+    /// 
+    /// The handler is dropped when the timeout elapses. No timeout by default.
+    pub fn handler_timeout(mut self, timeout: Duration) -> Self {
+        self.handler_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the HTTP/2 settings used for connections negotiating HTTP/2.
+    pub fn http2(mut self, http2: Http2Config) -> Self {
+        self.http2 = http2;
+        self
+    }
+
+    /// Serves plain TCP connections with HTTP/2 cleartext (h2c) using prior knowledge instead of HTTP/1.1.
+    /// 
+    /// HTTP/1.1 clients can not connect to the listener when enabled, which suits internal service meshes where
+    /// every client speaks HTTP/2. Connections using TLS are not affected. Disabled by default.
+    pub fn h2c(mut self, h2c: bool) -> Self {
+        self.h2c = h2c;
+        self
+    }
+
+    /// Keeps HTTP/1.1 connections open after a response to serve further requests from the same client.
+    /// 
+    /// Applies to both plain TCP and TLS connections, when disabled every response closes the connection. Enabled by default.
+    pub fn keep_alive(mut self, keep_alive: bool) -> Self {
+        self.keep_alive = keep_alive;
+        self
+    }
+
+    /// Sets the max time to receive the headers of an HTTP/1.1 request before the connection is closed.
+    /// 
+    /// Protects against slowloris clients holding connections open by sending headers slowly. No timeout by default.
+    pub fn header_read_timeout(mut self, timeout: Duration) -> Self {
+        self.header_read_timeout = Some(timeout);
+        self
+    }
+
     /// Enables TLS for incoming connections using the provided server certificate and private key in `.pem` format and
     /// configures the TLS context and sets supported ALPN protocols to allow HTTP/2 and HTTP/1.1.
     pub fn tls(mut self, tls_server_cert_path: impl AsRef<Path>, tls_server_key_path: impl AsRef<Path>) -> Self {
@@ -36,4 +139,43 @@ impl HttpServerConfig {
         self.tls_config = Some(tls_config);
         self
     }
-}
\ No newline at end of file
+}
+
+/// HTTP/2 connection settings, unset values use the hyper defaults.
+#[derive(Debug, Clone, Default)]
+pub struct Http2Config {
+    pub max_concurrent_streams: Option<u32>,
+    pub initial_stream_window_size: Option<u32>,
+    pub initial_connection_window_size: Option<u32>,
+    pub keep_alive_interval: Option<Duration>,
+}
+
+impl Http2Config {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the max number of concurrent streams a client may open on a connection.
+    pub fn max_concurrent_streams(mut self, max: u32) -> Self {
+        self.max_concurrent_streams = Some(max);
+        self
+    }
+
+    /// Sets the initial flow control window size in bytes of each stream.
+    pub fn initial_stream_window_size(mut self, size: u32) -> Self {
+        self.initial_stream_window_size = Some(size);
+        self
+    }
+
+    /// Sets the initial flow control window size in bytes of the connection.
+    pub fn initial_connection_window_size(mut self, size: u32) -> Self {
+        self.initial_connection_window_size = Some(size);
+        self
+    }
+
+    /// Sends a ping at the interval to keep idle connections alive and detect broken connections.
+    pub fn keep_alive_interval(mut self, interval: Duration) -> Self {
+        self.keep_alive_interval = Some(interval);
+        self
+    }
+}
@@ -1,4 +1,8 @@
 #[cfg(feature = "http")]
+mod http_idempotency_cache;
+#[cfg(feature = "http")]
+pub mod http_cors;
+#[cfg(feature = "http")]
 pub mod http_server;
 #[cfg(feature = "http")]
-pub mod http_server_config;
\ No newline at end of file
+pub mod http_server_config;
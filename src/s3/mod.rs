@@ -2,6 +2,14 @@
 pub mod s3_client;
 #[cfg(feature = "s3")]
 pub mod s3_client_config;
+#[cfg(feature = "s3")]
+pub mod s3_presigned_post;
+#[cfg(feature = "s3")]
+pub mod s3_delete_result;
+#[cfg(feature = "s3")]
+pub mod s3_object;
+#[cfg(feature = "s3")]
+pub mod s3_object_metadata;
 
 #[cfg(feature = "s3")]
 #[cfg(test)]
@@ -1,11 +1,16 @@
-use std::{marker::PhantomData, sync::Arc};
+use std::{collections::HashMap, io::SeekFrom, marker::PhantomData, path::Path, sync::Arc, time::Duration};
 
-use aws_config::{BehaviorVersion, Region, SdkConfig};
-use aws_sdk_s3::{Client, config::{Credentials, SharedCredentialsProvider}, types::{CompletedMultipartUpload, CompletedPart}};
+use aws_config::{BehaviorVersion, Region, SdkConfig, retry::RetryConfig, timeout::TimeoutConfig};
+use aws_sdk_s3::{Client, config::{Credentials, SharedCredentialsProvider}, presigning::PresigningConfig, primitives::ByteStream as SdkByteStream, types::{CompletedMultipartUpload, CompletedPart, Delete, ObjectIdentifier}};
 use bytes::{Bytes, BytesMut};
+use futures::{StreamExt, TryStreamExt};
+use time::OffsetDateTime;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
 use tokio_util::io::ReaderStream;
 
-use crate::{common::stream::ByteStream, s3::s3_client_config::S3ClientConfig};
+use base64::Engine;
+
+use crate::{common::{checksum::{ChecksumAlgorithm, ChecksumHasher}, stream::ByteStream}, s3::{s3_client_config::S3ClientConfig, s3_delete_result::S3DeleteResult, s3_object::S3Object, s3_object_metadata::S3ObjectMetadata, s3_presigned_post::{MAX_PRESIGN_EXPIRY, S3PresignedPost, S3PresignedPostConditions}}};
 
 pub struct NoBucket;
 pub struct HasBucket;
@@ -13,42 +18,86 @@ pub struct HasBucket;
 pub struct GetObject;
 pub struct PutObject;
 
+/// The default file size above which [`from_path`](S3Client::from_path) uses a multipart upload.
+pub const DEFAULT_MULTIPART_THRESHOLD: u64 = 16 * 1024 * 1024;
+/// The default size of each part in a multipart upload.
+pub const DEFAULT_PART_SIZE: u64 = 8 * 1024 * 1024;
+const MIN_PART_SIZE: u64 = 5 * 1024 * 1024;
+const MAX_PART_SIZE: u64 = 5 * 1024 * 1024 * 1024;
+const MAX_CONCURRENT_PARTS: usize = 4;
+
 pub struct S3Client<State> {
     client: Arc<Client>,
+    config: Arc<S3ClientConfig>,
     bucket: Option<String>,
     key: Option<String>,
+    multipart_threshold: u64,
+    part_size: u64,
+    content_type: Option<String>,
+    cache_control: Option<String>,
+    metadata: HashMap<String, String>,
     _state: PhantomData<State>,
 }
 
 impl S3Client<NoBucket> {
     pub fn new(config: S3ClientConfig) -> Self {
         Self {
-            client: Arc::new(Self::build_client(config)),
+            client: Arc::new(Self::build_client(config.clone())),
+            config: Arc::new(config),
             bucket: None,
             key: None,
+            multipart_threshold: DEFAULT_MULTIPART_THRESHOLD,
+            part_size: DEFAULT_PART_SIZE,
+            content_type: None,
+            cache_control: None,
+            metadata: HashMap::new(),
             _state: PhantomData
         }
     }
 
-    fn build_client(config: S3ClientConfig) -> Client {
+    pub(crate) fn build_client(config: S3ClientConfig) -> Client {
+        let path_style = config.path_style();
         let creds = Credentials::new(config.access_key.unwrap_or(String::new()), config.secret_key.unwrap_or(String::new()), None, None, "static");
         let provider = SharedCredentialsProvider::new(creds);
         let region = Region::new(config.region.unwrap_or(String::from("auto")));
         
-        let sdk_config = SdkConfig::builder()
+        let mut sdk_config = SdkConfig::builder()
         .region(region)
         .credentials_provider(provider)
         .behavior_version(BehaviorVersion::latest())
         .endpoint_url(config.endpoint.as_str());
 
-        Client::new(&sdk_config.build())
+        if let Some(max_retries) = config.max_retries {
+            sdk_config = sdk_config.retry_config(RetryConfig::standard().with_max_attempts(max_retries.saturating_add(1)));
+        }
+
+        if config.operation_timeout.is_some() || config.connect_timeout.is_some() {
+            // Timeouts left unset keep the SDK defaults, while setting `None` would disable them.
+            let mut timeout_config = TimeoutConfig::builder();
+            if let Some(timeout) = config.operation_timeout {
+                timeout_config = timeout_config.operation_timeout(timeout);
+            }
+            if let Some(timeout) = config.connect_timeout {
+                timeout_config = timeout_config.connect_timeout(timeout);
+            }
+            sdk_config = sdk_config.timeout_config(timeout_config.build());
+        }
+
+        let s3_config = aws_sdk_s3::config::Builder::from(&sdk_config.build()).force_path_style(path_style).build();
+        Client::from_conf(s3_config)
     }
 
     pub fn bucket(&self, bucket: impl Into<String>) -> S3Client<HasBucket> {
         S3Client {
             client: self.client.clone(),
+            config: self.config.clone(),
             bucket: Some(bucket.into()),
             key: None,
+            multipart_threshold: self.multipart_threshold,
+            part_size: self.part_size,
+            content_type: None,
+            cache_control: None,
+            metadata: HashMap::new(),
             _state: PhantomData
         }
     }
@@ -58,8 +107,14 @@ impl S3Client<HasBucket> {
     pub fn get_object(&self, key: impl Into<String>) -> S3Client<GetObject> {
         S3Client {
             client: self.client.clone(),
+            config: self.config.clone(),
             bucket: self.bucket.clone(),
             key: Some(key.into()),
+            multipart_threshold: self.multipart_threshold,
+            part_size: self.part_size,
+            content_type: None,
+            cache_control: None,
+            metadata: HashMap::new(),
             _state: PhantomData
         }
     }
@@ -67,12 +122,62 @@ impl S3Client<HasBucket> {
     pub fn put_object(&self, key: impl Into<String>) -> S3Client<PutObject> {
         S3Client {
             client: self.client.clone(),
+            config: self.config.clone(),
             bucket: self.bucket.clone(),
             key: Some(key.into()),
+            multipart_threshold: self.multipart_threshold,
+            part_size: self.part_size,
+            content_type: None,
+            cache_control: None,
+            metadata: HashMap::new(),
             _state: PhantomData
         }
     }
 
+    /// Creates a presigned POST for browser based uploads of keys starting with `key_prefix`.
+    /// 
+    /// Returns the form action url and the fields the form must include, the policy is signed with the configured credentials
+    /// and is valid for `expires_in`, which can not exceed 7 days.
+    pub fn presign_post(&self, key_prefix: impl AsRef<str>, conditions: S3PresignedPostConditions, expires_in: Duration) -> anyhow::Result<S3PresignedPost> {
+        S3PresignedPost::new(&self.config, self.bucket.as_ref().unwrap(), key_prefix.as_ref(), &conditions, expires_in)
+    }
+
+    /// Creates a presigned url for downloading the object with a plain GET request.
+    /// 
+    /// The url is valid for `expires_in`, which can not exceed 7 days.
+    pub async fn presign_get(&self, key: impl AsRef<str>, expires_in: Duration) -> anyhow::Result<String> {
+        let request = self.client
+            .get_object()
+            .bucket(self.bucket.as_ref().unwrap())
+            .key(key.as_ref())
+            .presigned(Self::presigning_config(expires_in)?)
+            .await?;
+
+        Ok(request.uri().to_string())
+    }
+
+    /// Creates a presigned url for uploading the object with a plain PUT request.
+    /// 
+    /// The url is valid for `expires_in`, which can not exceed 7 days.
+    pub async fn presign_put(&self, key: impl AsRef<str>, expires_in: Duration) -> anyhow::Result<String> {
+        let request = self.client
+            .put_object()
+            .bucket(self.bucket.as_ref().unwrap())
+            .key(key.as_ref())
+            .presigned(Self::presigning_config(expires_in)?)
+            .await?;
+
+        Ok(request.uri().to_string())
+    }
+
+    fn presigning_config(expires_in: Duration) -> anyhow::Result<PresigningConfig> {
+        if expires_in > MAX_PRESIGN_EXPIRY {
+            return Err(anyhow::anyhow!("Presigned url expiry can not exceed {:?}", MAX_PRESIGN_EXPIRY));
+        }
+
+        Ok(PresigningConfig::expires_in(expires_in)?)
+    }
+
     pub async fn delete_object(&self, key: impl AsRef<str>) -> anyhow::Result<()> {
         let _result = self.client
         .delete_object()
@@ -83,6 +188,157 @@ impl S3Client<HasBucket> {
 
         Ok(())
     }
+
+    /// Copies an object within the bucket on the server without downloading it.
+    pub async fn copy_object(&self, source_key: impl AsRef<str>, destination_key: impl AsRef<str>) -> anyhow::Result<()> {
+        self.copy_object_to(source_key, self.bucket.as_ref().unwrap(), destination_key).await
+    }
+
+    /// Copies an object from this bucket to a key in another bucket on the server without downloading it.
+    pub async fn copy_object_to(&self, source_key: impl AsRef<str>, destination_bucket: impl AsRef<str>, destination_key: impl AsRef<str>) -> anyhow::Result<()> {
+        let source = format!("{}/{}", self.bucket.as_ref().unwrap(), Self::encode_key(source_key.as_ref()));
+        self.client
+            .copy_object()
+            .copy_source(source)
+            .bucket(destination_bucket.as_ref())
+            .key(destination_key.as_ref())
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    /// Moves an object within the bucket by copying it on the server and then deleting the source.
+    pub async fn move_object(&self, source_key: impl AsRef<str>, destination_key: impl AsRef<str>) -> anyhow::Result<()> {
+        if source_key.as_ref() == destination_key.as_ref() {
+            return Err(anyhow::anyhow!("Source and destination of a move must not be the same key"));
+        }
+
+        self.copy_object(source_key.as_ref(), destination_key).await?;
+        self.delete_object(source_key).await
+    }
+
+    /// Percent-encodes a key for the `x-amz-copy-source` header, keeping `/` as the path separator.
+    fn encode_key(key: &str) -> String {
+        key.bytes().map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        }).collect()
+    }
+
+    /// Returns the metadata of the object without downloading it, or `None` if the key does not exist.
+    pub async fn head_object(&self, key: impl AsRef<str>) -> anyhow::Result<Option<S3ObjectMetadata>> {
+        let result = self.client
+            .head_object()
+            .bucket(self.bucket.as_ref().unwrap())
+            .key(key.as_ref())
+            .send()
+            .await;
+
+        let result = match result {
+            Ok(result) => result,
+            Err(err) if err.as_service_error().is_some_and(|err| err.is_not_found()) => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(Some(S3ObjectMetadata {
+            size: result.content_length().and_then(|size| u64::try_from(size).ok()).unwrap_or(0),
+            content_type: result.content_type().map(String::from),
+            last_modified: result.last_modified().and_then(|date| OffsetDateTime::from_unix_timestamp_nanos(date.as_nanos()).ok()),
+            etag: result.e_tag().map(String::from),
+        }))
+    }
+
+    /// Lists the objects in the bucket without downloading them, optionally only keys starting with `prefix`.
+    /// 
+    /// Pages through the listing until all objects are listed or `max_keys` objects have been listed.
+    pub async fn list_objects(&self, prefix: Option<&str>, max_keys: Option<usize>) -> anyhow::Result<Vec<S3Object>> {
+        let mut objects = Vec::new();
+        let mut pages = self.client
+            .list_objects_v2()
+            .bucket(self.bucket.as_ref().unwrap())
+            .set_prefix(prefix.map(String::from))
+            .into_paginator()
+            .send();
+
+        while let Some(page) = pages.next().await {
+            for object in page?.contents() {
+                if max_keys.is_some_and(|max_keys| objects.len() >= max_keys) {
+                    return Ok(objects);
+                }
+
+                objects.push(S3Object {
+                    key: object.key().unwrap_or_default().to_string(),
+                    size: object.size().and_then(|size| u64::try_from(size).ok()).unwrap_or(0),
+                    last_modified: object.last_modified().and_then(|date| OffsetDateTime::from_unix_timestamp_nanos(date.as_nanos()).ok()),
+                });
+            }
+        }
+
+        Ok(objects)
+    }
+
+    /// Deletes all objects with keys starting with `prefix`, listing and deleting in batches of up to 1000 keys.
+    /// 
+    /// The prefix must not be empty to avoid accidentally deleting the whole bucket, use [`delete_all`](Self::delete_all) for that.
+    pub async fn delete_prefix(&self, prefix: impl AsRef<str>) -> anyhow::Result<S3DeleteResult> {
+        if prefix.as_ref().is_empty() {
+            return Err(anyhow::anyhow!("Prefix must not be empty, use delete_all to delete all objects"));
+        }
+
+        self.delete_listed(prefix.as_ref()).await
+    }
+
+    /// Deletes all objects in the bucket, listing and deleting in batches of up to 1000 keys.
+    pub async fn delete_all(&self) -> anyhow::Result<S3DeleteResult> {
+        self.delete_listed("").await
+    }
+
+    /// Deletes the objects with the keys, sending a request for every batch of up to 1000 keys.
+    /// 
+    /// Keys that do not exist are reported as deleted, as S3 treats deleting a missing key as a success.
+    pub async fn delete_objects(&self, keys: Vec<String>) -> anyhow::Result<S3DeleteResult> {
+        self.delete_batch(keys).await
+    }
+
+    async fn delete_listed(&self, prefix: &str) -> anyhow::Result<S3DeleteResult> {
+        let mut result = S3DeleteResult::default();
+        let mut pages = self.client
+            .list_objects_v2()
+            .bucket(self.bucket.as_ref().unwrap())
+            .prefix(prefix)
+            .into_paginator()
+            .send();
+
+        while let Some(page) = pages.next().await {
+            let keys: Vec<String> = page?.contents().iter().filter_map(|object| object.key().map(String::from)).collect();
+            let batch = self.delete_batch(keys).await?;
+            result.deleted.extend(batch.deleted);
+            result.failed.extend(batch.failed);
+        }
+
+        Ok(result)
+    }
+
+    async fn delete_batch(&self, keys: Vec<String>) -> anyhow::Result<S3DeleteResult> {
+        let mut result = S3DeleteResult::default();
+
+        for chunk in keys.chunks(1000) {
+            let objects = chunk.iter().map(|key| ObjectIdentifier::builder().key(key).build()).collect::<Result<Vec<_>, _>>()?;
+            let delete = Delete::builder().set_objects(Some(objects)).quiet(false).build()?;
+            let response = self.client
+                .delete_objects()
+                .bucket(self.bucket.as_ref().unwrap())
+                .delete(delete)
+                .send()
+                .await?;
+
+            result.deleted.extend(response.deleted().iter().filter_map(|deleted| deleted.key().map(String::from)));
+            result.failed.extend(response.errors().iter().map(|error| (error.key().unwrap_or_default().to_string(), error.message().unwrap_or_default().to_string())));
+        }
+
+        Ok(result)
+    }
 }
 
 impl S3Client<GetObject> {
@@ -97,6 +353,7 @@ impl S3Client<GetObject> {
         Ok(result.body.collect().await?.into_bytes())
     }
 
+    /// Returns the object as a stream with the content-length and content-type of the object, if known.
     pub async fn as_stream(&self) -> anyhow::Result<ByteStream> {
         let result = self.client
             .get_object()
@@ -105,44 +362,221 @@ impl S3Client<GetObject> {
             .send()
             .await?;
 
-        let stream = ReaderStream::new(result.body.into_async_read());
-        Ok(ByteStream::new(stream))
+        let content_length = result.content_length.and_then(|length| u64::try_from(length).ok());
+        let content_type = result.content_type;
+        let mut stream = ByteStream::new(ReaderStream::new(result.body.into_async_read()));
+        if let Some(content_length) = content_length {
+            stream = stream.with_content_length(content_length);
+        }
+        if let Some(content_type) = content_type {
+            stream = stream.with_content_type(content_type);
+        }
+
+        Ok(stream)
+    }
+
+    /// Streams the object into the writer without buffering the whole object, returns the number of bytes written.
+    pub async fn to_writer<W>(&self, writer: &mut W) -> anyhow::Result<u64>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let result = self.client
+            .get_object()
+            .bucket(self.bucket.as_ref().unwrap())
+            .key(self.key.as_ref().unwrap())
+            .send()
+            .await?;
+
+        let mut reader = result.body.into_async_read();
+        let bytes = tokio::io::copy(&mut reader, writer).await?;
+        writer.flush().await?;
+
+        Ok(bytes)
     }
 }
 
 impl S3Client<PutObject> {
+    /// Sets the file size above which [`from_path`](Self::from_path) uses a multipart upload, defaults to [`DEFAULT_MULTIPART_THRESHOLD`].
+    pub fn multipart_threshold(mut self, bytes: u64) -> Self {
+        self.multipart_threshold = bytes;
+        self
+    }
+
+    /// Sets the size of each part in a multipart upload, defaults to [`DEFAULT_PART_SIZE`].
+    /// 
+    /// S3 requires all parts except the last to be between 5 MiB and 5 GiB, sizes outside this range are clamped to it.
+    pub fn part_size(mut self, bytes: u64) -> Self {
+        self.part_size = bytes.clamp(MIN_PART_SIZE, MAX_PART_SIZE);
+        self
+    }
+
+    /// Sets the content-type of the object, S3 defaults to `application/octet-stream`.
+    pub fn content_type(mut self, content_type: impl AsRef<str>) -> Self {
+        self.content_type = Some(content_type.as_ref().to_string());
+        self
+    }
+
+    /// Sets the cache-control header returned when the object is downloaded.
+    pub fn cache_control(mut self, cache_control: impl AsRef<str>) -> Self {
+        self.cache_control = Some(cache_control.as_ref().to_string());
+        self
+    }
+
+    /// Adds user metadata to the object, stored as `x-amz-meta-<key>`.
+    pub fn metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
     pub async fn from_bytes(&self, bytes: impl Into<Bytes>) -> anyhow::Result<()> {
-        let bytes = bytes.into();
-        let _result = self.client
+        self.upload_bytes(bytes.into(), None).await?;
+        Ok(())
+    }
+
+    /// Uploads the bytes, returning the checksum of the content as lowercase hex.
+    /// 
+    /// The checksum is also sent, `x-amz-checksum-sha256` or `Content-MD5`, for S3 to verify the content on its end.
+    pub async fn from_bytes_with_checksum(&self, bytes: impl Into<Bytes>, algorithm: ChecksumAlgorithm) -> anyhow::Result<String> {
+        Ok(self.upload_bytes(bytes.into(), Some(algorithm)).await?.unwrap_or_default())
+    }
+
+    /// Uploads a local file, files larger than the multipart threshold are uploaded in parts concurrently.
+    /// 
+    /// A failed multipart upload is aborted so no incomplete upload is left behind.
+    pub async fn from_path(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        self.upload_path(path.as_ref(), None).await?;
+        Ok(())
+    }
+
+    /// Uploads a local file like [`from_path`](Self::from_path), returning the checksum of the content as lowercase hex.
+    /// 
+    /// Files uploaded in a single request are hashed in a streaming pass before the upload, as the checksum is sent
+    /// for S3 to verify. The checksum of a multipart upload is computed while the parts are uploaded and only returned.
+    pub async fn from_path_with_checksum(&self, path: impl AsRef<Path>, algorithm: ChecksumAlgorithm) -> anyhow::Result<String> {
+        Ok(self.upload_path(path.as_ref(), Some(algorithm)).await?.unwrap_or_default())
+    }
+
+    pub async fn from_stream(&self, stream: ByteStream) -> anyhow::Result<()> {
+        self.upload_stream(stream, None).await?;
+        Ok(())
+    }
+
+    /// Uploads the stream in parts, returning the checksum of the content as lowercase hex computed while uploading.
+    pub async fn from_stream_with_checksum(&self, stream: ByteStream, algorithm: ChecksumAlgorithm) -> anyhow::Result<String> {
+        Ok(self.upload_stream(stream, Some(algorithm)).await?.unwrap_or_default())
+    }
+
+    async fn upload_bytes(&self, bytes: Bytes, checksum: Option<ChecksumAlgorithm>) -> anyhow::Result<Option<String>> {
+        let digest = checksum.map(|algorithm| {
+            let mut hasher = ChecksumHasher::new(algorithm);
+            hasher.update(&bytes);
+            (algorithm, hasher.finalize())
+        });
+
+        self.put(bytes.into(), digest.as_ref()).await?;
+        Ok(digest.map(|(_, digest)| ChecksumHasher::to_hex(&digest)))
+    }
+
+    async fn upload_path(&self, path: &Path, checksum: Option<ChecksumAlgorithm>) -> anyhow::Result<Option<String>> {
+        let size = tokio::fs::metadata(path).await?.len();
+
+        if size <= self.multipart_threshold {
+            // The checksum header must be sent before the body, so the file is hashed before it is uploaded.
+            let digest = match checksum {
+                Some(algorithm) => Some((algorithm, Self::hash_file(path, algorithm).await?)),
+                None => None,
+            };
+
+            self.put(SdkByteStream::from_path(path).await?, digest.as_ref()).await?;
+            return Ok(digest.map(|(_, digest)| ChecksumHasher::to_hex(&digest)));
+        }
+
+        let mut hasher = checksum.map(ChecksumHasher::new);
+        let upload_id = self.create_upload().await?;
+        let result = self.multipart_upload_file(&upload_id, path, size, &mut hasher).await;
+        self.finish_upload(&upload_id, result).await?;
+        Ok(hasher.map(ChecksumHasher::finalize_hex))
+    }
+
+    async fn upload_stream(&self, stream: ByteStream, checksum: Option<ChecksumAlgorithm>) -> anyhow::Result<Option<String>> {
+        let mut hasher = checksum.map(ChecksumHasher::new);
+        let upload_id = self.create_upload().await?;
+        let result = self.multipart_upload(&upload_id, stream, &mut hasher).await;
+        self.finish_upload(&upload_id, result).await?;
+        Ok(hasher.map(ChecksumHasher::finalize_hex))
+    }
+
+    /// Uploads the object in a single request, sending the checksum for S3 to verify when given.
+    async fn put(&self, body: SdkByteStream, digest: Option<&(ChecksumAlgorithm, Vec<u8>)>) -> anyhow::Result<()> {
+        let mut request = self.client
             .put_object()
             .bucket(self.bucket.as_ref().unwrap())
             .key(self.key.as_ref().unwrap())
-            .body(bytes.into())
-            .send()
-            .await?;
+            .set_content_type(self.content_type.clone())
+            .set_cache_control(self.cache_control.clone())
+            .set_metadata(self.object_metadata());
+
+        if let Some((algorithm, digest)) = digest {
+            let encoded = base64::engine::general_purpose::STANDARD.encode(digest);
+            request = match algorithm {
+                ChecksumAlgorithm::Sha256 => request.checksum_sha256(encoded),
+                ChecksumAlgorithm::Md5 => request.content_md5(encoded),
+            };
+        }
 
+        let _result = request.body(body).send().await?;
         Ok(())
     }
 
-    pub async fn from_stream(&self, stream: ByteStream) -> anyhow::Result<()> {
-        let bucket = self.bucket.as_ref().unwrap();
-        let key = self.key.as_ref().unwrap();
+    /// Reads the file in chunks to compute its digest without holding it in memory.
+    async fn hash_file(path: &Path, algorithm: ChecksumAlgorithm) -> anyhow::Result<Vec<u8>> {
+        let mut hasher = ChecksumHasher::new(algorithm);
+        let mut file = tokio::fs::File::open(path).await?;
+        let mut buffer = vec![0u8; 64 * 1024];
+        loop {
+            let read = file.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+        }
 
+        Ok(hasher.finalize())
+    }
+
+    fn object_metadata(&self) -> Option<HashMap<String, String>> {
+        match self.metadata.is_empty() {
+            true => None,
+            false => Some(self.metadata.clone()),
+        }
+    }
+
+    async fn create_upload(&self) -> anyhow::Result<String> {
         let create_res = self.client
             .create_multipart_upload()
-            .bucket(bucket)
-            .key(key)
+            .bucket(self.bucket.as_ref().unwrap())
+            .key(self.key.as_ref().unwrap())
+            .set_content_type(self.content_type.clone())
+            .set_cache_control(self.cache_control.clone())
+            .set_metadata(self.object_metadata())
             .send()
             .await?;
         
-        let upload_id = create_res.upload_id().ok_or_else(|| anyhow::anyhow!("No upload ID"))?;
-        let upload_result = self.multipart_upload(upload_id, stream).await;
+        Ok(create_res.upload_id().ok_or_else(|| anyhow::anyhow!("No upload ID"))?.to_string())
+    }
+
+    /// Completes the upload with the uploaded parts, or aborts it if any part failed.
+    async fn finish_upload(&self, upload_id: &str, parts: anyhow::Result<Vec<CompletedPart>>) -> anyhow::Result<()> {
+        let result = match parts {
+            Ok(parts) => self.complete_upload(upload_id, parts).await,
+            Err(err) => Err(err),
+        };
 
-        if let Err(err) = upload_result {
+        if let Err(err) = result {
             let _result = self.client
             .abort_multipart_upload()
-            .bucket(bucket)
-            .key(key)
+            .bucket(self.bucket.as_ref().unwrap())
+            .key(self.key.as_ref().unwrap())
             .upload_id(upload_id)
             .send()
             .await;
@@ -153,19 +587,37 @@ impl S3Client<PutObject> {
         Ok(())
     }
 
-    async fn multipart_upload(&self, upload_id: &str, mut stream: ByteStream) -> anyhow::Result<()> {
-        let bucket = self.bucket.as_ref().unwrap();
-        let key = self.key.as_ref().unwrap();
-        let min_part_size: usize = 5 * 1024 * 1024;
+    async fn complete_upload(&self, upload_id: &str, parts: Vec<CompletedPart>) -> anyhow::Result<()> {
+        let completed_upload = CompletedMultipartUpload::builder()
+            .set_parts(Some(parts))
+            .build();
+
+        self.client
+            .complete_multipart_upload()
+            .bucket(self.bucket.as_ref().unwrap())
+            .key(self.key.as_ref().unwrap())
+            .upload_id(upload_id)
+            .multipart_upload(completed_upload)
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    async fn multipart_upload(&self, upload_id: &str, mut stream: ByteStream, hasher: &mut Option<ChecksumHasher>) -> anyhow::Result<Vec<CompletedPart>> {
+        let part_size = self.part_size as usize;
         let mut completed_parts = Vec::new();
         let mut part_number = 1;
-        let mut buffer = BytesMut::with_capacity(min_part_size);
+        let mut buffer = BytesMut::with_capacity(part_size);
 
         while let Some(chunk) = stream.next().await {
             let chunk = chunk?;
+            if let Some(hasher) = hasher {
+                hasher.update(&chunk);
+            }
             buffer.extend_from_slice(&chunk);
 
-            if buffer.len() >= min_part_size {
+            if buffer.len() >= part_size {
                 let part = self.upload_part(upload_id, part_number, buffer.split_off(0).into()).await?;
                 completed_parts.push(part);
                 part_number += 1;
@@ -177,20 +629,35 @@ impl S3Client<PutObject> {
             completed_parts.push(part);
         }
 
-        let completed_upload = CompletedMultipartUpload::builder()
-            .set_parts(Some(completed_parts))
-            .build();
+        Ok(completed_parts)
+    }
 
-        self.client
-            .complete_multipart_upload()
-            .bucket(bucket)
-            .key(key)
-            .upload_id(upload_id)
-            .multipart_upload(completed_upload)
-            .send()
-            .await?;
+    /// Uploads parts concurrently, the parts complete in order so the checksum is updated in order.
+    async fn multipart_upload_file(&self, upload_id: &str, path: &Path, size: u64, hasher: &mut Option<ChecksumHasher>) -> anyhow::Result<Vec<CompletedPart>> {
+        let part_count = size.div_ceil(self.part_size);
+        let hashing = hasher.is_some();
+        let mut parts = futures::stream::iter(0..part_count)
+            .map(|index| async move {
+                let offset = index * self.part_size;
+                let length = self.part_size.min(size - offset) as usize;
+                let mut file = tokio::fs::File::open(path).await?;
+                file.seek(SeekFrom::Start(offset)).await?;
+                let mut buffer = vec![0u8; length];
+                file.read_exact(&mut buffer).await?;
+                let buffer = Bytes::from(buffer);
+                let part = self.upload_part(upload_id, index as i32 + 1, buffer.clone()).await?;
+                anyhow::Ok((part, hashing.then_some(buffer)))
+            })
+            .buffered(MAX_CONCURRENT_PARTS);
 
-        Ok(())
+        let mut completed_parts = Vec::new();
+        while let Some((part, buffer)) = parts.try_next().await? {
+            if let (Some(hasher), Some(buffer)) = (hasher.as_mut(), buffer) {
+                hasher.update(&buffer);
+            }
+            completed_parts.push(part);
+        }
+        Ok(completed_parts)
     }
 
     async fn upload_part(&self, upload_id: &str, part_number: i32, bytes: bytes::Bytes) -> anyhow::Result<CompletedPart> {
@@ -206,4 +673,4 @@ impl S3Client<PutObject> {
 
         Ok(CompletedPart::builder().e_tag(upload_part_res.e_tag().unwrap_or_default()).part_number(part_number).build())
     }
-}
\ No newline at end of file
+}
@@ -1,4 +1,4 @@
-use std::marker::PhantomData;
+use std::{marker::PhantomData, time::Duration};
 
 #[derive(Debug, Clone)]
 pub struct S3ClientConfig {
@@ -6,6 +6,10 @@ pub struct S3ClientConfig {
     pub region: Option<String>,
     pub access_key: Option<String>,
     pub secret_key: Option<String>,
+    pub max_retries: Option<u32>,
+    pub operation_timeout: Option<Duration>,
+    pub connect_timeout: Option<Duration>,
+    pub force_path_style: Option<bool>,
 }
 impl S3ClientConfig {
     pub fn builder() -> S3ClientConfigBuilder<SetEndpoint> {
@@ -14,9 +18,21 @@ impl S3ClientConfig {
             region: None,
             access_key: None,
             secret_key: None,
+            max_retries: None,
+            operation_timeout: None,
+            connect_timeout: None,
+            force_path_style: None,
             _state: PhantomData
         }
     }
+    /// Whether buckets are addressed in the path, see [`force_path_style`](S3ClientConfigBuilder::force_path_style).
+    pub(crate) fn path_style(&self) -> bool {
+        self.force_path_style.unwrap_or_else(|| {
+            let host = self.endpoint.split_once("://").map_or(self.endpoint.as_str(), |(_, rest)| rest);
+            let host = host.split(['/', ':']).next().unwrap_or_default().to_ascii_lowercase();
+            !(host.ends_with(".amazonaws.com") || host.ends_with(".amazonaws.com.cn"))
+        })
+    }
 }
 
 pub struct SetEndpoint;
@@ -27,6 +43,10 @@ pub struct S3ClientConfigBuilder<State> {
     pub region: Option<String>,
     pub access_key: Option<String>,
     pub secret_key: Option<String>,
+    pub max_retries: Option<u32>,
+    pub operation_timeout: Option<Duration>,
+    pub connect_timeout: Option<Duration>,
+    pub force_path_style: Option<bool>,
     _state: PhantomData<State>,
 }
 
@@ -37,6 +57,10 @@ impl S3ClientConfigBuilder<SetEndpoint> {
             region: self.region,
             access_key: self.access_key,
             secret_key: self.secret_key,
+            max_retries: self.max_retries,
+            operation_timeout: self.operation_timeout,
+            connect_timeout: self.connect_timeout,
+            force_path_style: self.force_path_style,
             _state: PhantomData
         }
     }
@@ -58,12 +82,48 @@ impl S3ClientConfigBuilder<Optional> {
         self
     }
 
+    /// Sets the max number of retries of failed requests, `0` disables retries.
+    /// 
+    /// Defaults to the AWS SDK standard retry behavior of 3 attempts with exponential backoff.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = Some(max_retries);
+        self
+    }
+
+    /// Sets the max time of an operation including all retries, e.g. an upload or a multipart part.
+    /// 
+    /// No timeout by default.
+    pub fn operation_timeout(mut self, timeout: Duration) -> Self {
+        self.operation_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the max time to open a connection to the endpoint.
+    /// 
+    /// Defaults to the AWS SDK connect timeout.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Addresses buckets in the path as `endpoint/bucket/key` instead of the host as `bucket.endpoint/key`.
+    /// 
+    /// Defaults to path-style for S3 compatible stores such as MinIO and Ceph, and virtual-hosted style for `amazonaws.com` endpoints.
+    pub fn force_path_style(mut self, force_path_style: bool) -> Self {
+        self.force_path_style = Some(force_path_style);
+        self
+    }
+
     pub fn build(self) -> anyhow::Result<S3ClientConfig> {
         Ok(S3ClientConfig {
             endpoint: self.endpoint.ok_or_else(|| anyhow::anyhow!("Endpoint not found"))?,
             region: self.region,
             access_key: self.access_key,
-            secret_key: self.secret_key
+            secret_key: self.secret_key,
+            max_retries: self.max_retries,
+            operation_timeout: self.operation_timeout,
+            connect_timeout: self.connect_timeout,
+            force_path_style: self.force_path_style,
         })
     }
 }
\ No newline at end of file
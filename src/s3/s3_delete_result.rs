@@ -0,0 +1,6 @@
+/// Result of a batched delete, listing deleted keys and the keys that failed with the reason.
+#[derive(Debug, Clone, Default)]
+pub struct S3DeleteResult {
+    pub deleted: Vec<String>,
+    pub failed: Vec<(String, String)>,
+}
@@ -0,0 +1,9 @@
+use time::OffsetDateTime;
+
+/// An object in a bucket listing.
+#[derive(Debug, Clone)]
+pub struct S3Object {
+    pub key: String,
+    pub size: u64,
+    pub last_modified: Option<OffsetDateTime>,
+}
@@ -0,0 +1,10 @@
+use time::OffsetDateTime;
+
+/// Metadata of an object returned by [`head_object`](crate::s3::s3_client::S3Client::head_object).
+#[derive(Debug, Clone)]
+pub struct S3ObjectMetadata {
+    pub size: u64,
+    pub content_type: Option<String>,
+    pub last_modified: Option<OffsetDateTime>,
+    pub etag: Option<String>,
+}
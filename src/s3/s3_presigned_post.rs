@@ -0,0 +1,135 @@
+use std::time::{Duration, SystemTime};
+
+use base64::Engine;
+use time::OffsetDateTime;
+
+use crate::s3::s3_client_config::S3ClientConfig;
+
+/// Max expiry supported by S3 for presigned requests.
+pub const MAX_PRESIGN_EXPIRY: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Conditions embedded in the policy of a presigned POST.
+#[derive(Debug, Clone, Default)]
+pub struct S3PresignedPostConditions {
+    content_length_range: Option<(u64, u64)>,
+    content_type: Option<String>,
+    content_type_starts_with: Option<String>,
+}
+
+impl S3PresignedPostConditions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restricts the size of the uploaded object to `min..=max` bytes.
+    pub fn content_length_range(mut self, min: u64, max: u64) -> Self {
+        self.content_length_range = Some((min, max));
+        self
+    }
+
+    /// Requires the `Content-Type` form field to be exactly the given value, the field is included in the returned fields.
+    pub fn content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    /// Requires the `Content-Type` form field to start with the given value, e.g. `image/`.
+    pub fn content_type_starts_with(mut self, prefix: impl Into<String>) -> Self {
+        self.content_type_starts_with = Some(prefix.into());
+        self
+    }
+}
+
+/// A presigned POST for browser based uploads.
+///
+/// The client submits a `multipart/form-data` POST to `url` containing all `fields` followed by the `file` field.
+#[derive(Debug, Clone)]
+pub struct S3PresignedPost {
+    pub url: String,
+    pub fields: Vec<(String, String)>,
+}
+
+impl S3PresignedPost {
+    pub(crate) fn new(config: &S3ClientConfig, bucket: &str, key_prefix: &str, conditions: &S3PresignedPostConditions, expires_in: Duration) -> anyhow::Result<Self> {
+        if expires_in > MAX_PRESIGN_EXPIRY {
+            return Err(anyhow::anyhow!("Presigned POST expiry can not exceed {:?}", MAX_PRESIGN_EXPIRY));
+        }
+
+        let access_key = config.access_key.clone().unwrap_or_default();
+        let secret_key = config.secret_key.clone().unwrap_or_default();
+        let region = config.region.clone().unwrap_or(String::from("auto"));
+
+        let now = SystemTime::now();
+        let date_time = OffsetDateTime::from(now);
+        let date = format!("{:04}{:02}{:02}", date_time.year(), date_time.month() as u8, date_time.day());
+        let amz_date = format!("{}T{:02}{:02}{:02}Z", date, date_time.hour(), date_time.minute(), date_time.second());
+        let credential = format!("{}/{}/{}/s3/aws4_request", access_key, date, region);
+
+        let expiration = date_time + expires_in;
+        let expiration = format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.000Z", expiration.year(), expiration.month() as u8, expiration.day(), expiration.hour(), expiration.minute(), expiration.second());
+
+        let mut policy_conditions = vec![
+            format!("{{\"bucket\":{}}}", Self::json_string(bucket)),
+            format!("[\"starts-with\",\"$key\",{}]", Self::json_string(key_prefix)),
+            String::from("{\"x-amz-algorithm\":\"AWS4-HMAC-SHA256\"}"),
+            format!("{{\"x-amz-credential\":{}}}", Self::json_string(&credential)),
+            format!("{{\"x-amz-date\":{}}}", Self::json_string(&amz_date)),
+        ];
+
+        let mut fields = vec![(String::from("key"), format!("{}${{filename}}", key_prefix))];
+
+        if let Some((min, max)) = conditions.content_length_range {
+            policy_conditions.push(format!("[\"content-length-range\",{},{}]", min, max));
+        }
+        if let Some(content_type) = &conditions.content_type {
+            policy_conditions.push(format!("{{\"Content-Type\":{}}}", Self::json_string(content_type)));
+            fields.push((String::from("Content-Type"), content_type.clone()));
+        }
+        if let Some(prefix) = &conditions.content_type_starts_with {
+            policy_conditions.push(format!("[\"starts-with\",\"$Content-Type\",{}]", Self::json_string(prefix)));
+        }
+
+        let policy = format!("{{\"expiration\":\"{}\",\"conditions\":[{}]}}", expiration, policy_conditions.join(","));
+        let policy = base64::engine::general_purpose::STANDARD.encode(policy);
+
+        let signing_key = aws_sigv4::sign::v4::generate_signing_key(&secret_key, now, &region, "s3");
+        let signature = aws_sigv4::sign::v4::calculate_signature(signing_key, policy.as_bytes());
+
+        fields.push((String::from("x-amz-algorithm"), String::from("AWS4-HMAC-SHA256")));
+        fields.push((String::from("x-amz-credential"), credential));
+        fields.push((String::from("x-amz-date"), amz_date));
+        fields.push((String::from("policy"), policy));
+        fields.push((String::from("x-amz-signature"), signature));
+
+        Ok(S3PresignedPost {
+            url: Self::bucket_url(config, bucket),
+            fields,
+        })
+    }
+
+    fn bucket_url(config: &S3ClientConfig, bucket: &str) -> String {
+        let endpoint = config.endpoint.trim_end_matches('/');
+        match (config.path_style(), endpoint.split_once("://")) {
+            (false, Some((scheme, host))) => format!("{}://{}.{}", scheme, bucket, host),
+            _ => format!("{}/{}", endpoint, bucket),
+        }
+    }
+
+    fn json_string(value: &str) -> String {
+        let mut escaped = String::with_capacity(value.len() + 2);
+        escaped.push('"');
+        for c in value.chars() {
+            match c {
+                '"' => escaped.push_str("\\\""),
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                '\t' => escaped.push_str("\\t"),
+                c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+                c => escaped.push(c),
+            }
+        }
+        escaped.push('"');
+        escaped
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{common::stream::ByteStream, s3::{s3_client::S3Client, s3_client_config::S3ClientConfig}};
+use crate::{common::{checksum::ChecksumAlgorithm, stream::ByteStream}, s3::{s3_client::S3Client, s3_client_config::S3ClientConfig}};
 
 #[tokio::test]
 async fn client_test() {
@@ -21,8 +21,268 @@ async fn client_test() {
 
     let result = client.bucket("test").get_object("test.txt").as_stream().await;
     assert!(result.is_ok());
-    tracing::info!("{:?}", result.unwrap().as_bytes().await);
+    tracing::info!("{:?}", result.unwrap().to_bytes().await);
 
     let result = client.bucket("test").delete_object("test.txt").await;
     assert!(result.is_ok());
-}
\ No newline at end of file
+}
+
+#[test]
+fn presign_post_test() {
+    use std::time::Duration;
+    use crate::s3::s3_presigned_post::S3PresignedPostConditions;
+
+    let config = S3ClientConfig::builder().endpoint("http://127.0.0.1:9000").region("us-east-1").access_key("minioadmin").secret_key("minioadmin").build().unwrap();
+    let client = S3Client::new(config);
+
+    let conditions = S3PresignedPostConditions::new().content_length_range(1, 1024).content_type("text/plain");
+    let post = client.bucket("test").presign_post("uploads/", conditions, Duration::from_secs(3600)).unwrap();
+    assert_eq!(post.url, "http://127.0.0.1:9000/test");
+    assert!(post.fields.iter().any(|(k, v)| k == "key" && v == "uploads/${filename}"));
+    assert!(post.fields.iter().any(|(k, v)| k == "Content-Type" && v == "text/plain"));
+    assert!(post.fields.iter().any(|(k, v)| k == "x-amz-signature" && v.len() == 64));
+
+    let conditions = S3PresignedPostConditions::new();
+    assert!(client.bucket("test").presign_post("uploads/", conditions, Duration::from_secs(8 * 24 * 60 * 60)).is_err());
+}
+
+#[tokio::test]
+async fn delete_prefix_test() {
+    let config = S3ClientConfig::builder().endpoint("http://127.0.0.1:9000").access_key("minioadmin").secret_key("minioadmin").build().unwrap();
+    let client = S3Client::new(config);
+
+    let result = client.bucket("test").delete_prefix("").await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn multipart_test() {
+    let config = S3ClientConfig::builder().endpoint("http://127.0.0.1:9000").access_key("minioadmin").secret_key("minioadmin").build().unwrap();
+    let client = S3Client::new(config);
+
+    tokio::fs::write("/tmp/s3_multipart_test.bin", vec![7u8; 12 * 1024 * 1024]).await.unwrap();
+    let result = client.bucket("test").put_object("multipart.bin").multipart_threshold(5 * 1024 * 1024).part_size(5 * 1024 * 1024).from_path("/tmp/s3_multipart_test.bin").await;
+    assert!(result.is_ok());
+
+    let result = client.bucket("test").get_object("multipart.bin").as_bytes().await;
+    assert_eq!(result.unwrap().len(), 12 * 1024 * 1024);
+
+    let chunks = || ByteStream::new(futures::stream::iter((0..12).map(|_| Ok::<_, std::io::Error>(bytes::Bytes::from(vec![7u8; 1024 * 1024])))));
+    let result = client.bucket("test").put_object("multipart.bin").part_size(6 * 1024 * 1024).from_stream(chunks()).await;
+    assert!(result.is_ok());
+    let metadata = client.bucket("test").head_object("multipart.bin").await.unwrap().unwrap();
+    assert!(metadata.etag.unwrap().trim_matches('"').ends_with("-2"));
+
+    let result = client.bucket("test").put_object("multipart.bin").part_size(1024).from_stream(chunks()).await;
+    assert!(result.is_ok());
+    let metadata = client.bucket("test").head_object("multipart.bin").await.unwrap().unwrap();
+    assert!(metadata.etag.unwrap().trim_matches('"').ends_with("-3"));
+
+    let result = client.bucket("test").delete_object("multipart.bin").await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn presign_url_test() {
+    use std::time::Duration;
+
+    let config = S3ClientConfig::builder().endpoint("http://127.0.0.1:9000").region("us-east-1").access_key("minioadmin").secret_key("minioadmin").build().unwrap();
+    let client = S3Client::new(config);
+
+    let url = client.bucket("test").presign_get("test.txt", Duration::from_secs(3600)).await.unwrap();
+    assert!(url.starts_with("http://"));
+    assert!(url.contains("127.0.0.1:9000"));
+    assert!(url.contains("X-Amz-Signature="));
+    assert!(url.contains("X-Amz-Expires=3600"));
+
+    let url = client.bucket("test").presign_put("test.txt", Duration::from_secs(60)).await.unwrap();
+    assert!(url.contains("X-Amz-Signature="));
+    assert!(url.contains("X-Amz-Expires=60"));
+
+    let result = client.bucket("test").presign_get("test.txt", Duration::from_secs(8 * 24 * 60 * 60)).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn list_objects_test() {
+    let config = S3ClientConfig::builder().endpoint("http://127.0.0.1:9000").access_key("minioadmin").secret_key("minioadmin").build().unwrap();
+    let client = S3Client::new(config);
+    let bucket = client.bucket("test");
+
+    for (key, body) in [("list/a.txt", "a"), ("list/b.txt", "bb"), ("list/c.txt", "ccc")] {
+        assert!(bucket.put_object(key).from_bytes(body).await.is_ok());
+    }
+
+    let objects = bucket.list_objects(Some("list/"), None).await.unwrap();
+    assert_eq!(objects.len(), 3);
+    for (key, size) in [("list/a.txt", 1), ("list/b.txt", 2), ("list/c.txt", 3)] {
+        assert!(objects.iter().any(|object| object.key == key && object.size == size));
+    }
+
+    let objects = bucket.list_objects(Some("list/"), Some(2)).await.unwrap();
+    assert_eq!(objects.len(), 2);
+
+    assert!(bucket.delete_prefix("list/").await.is_ok());
+}
+
+#[tokio::test]
+async fn put_object_headers_test() {
+    let config = S3ClientConfig::builder().endpoint("http://127.0.0.1:9000").access_key("minioadmin").secret_key("minioadmin").build().unwrap();
+    let client = S3Client::new(config);
+
+    let result = client.bucket("test").put_object("image.png").content_type("image/png").cache_control("max-age=60").metadata("source", "test").from_bytes("png").await;
+    assert!(result.is_ok());
+
+    let result = client.bucket("test").get_object("image.png").as_stream().await;
+    assert_eq!(result.unwrap().content_type(), Some("image/png"));
+
+    let result = client.bucket("test").delete_object("image.png").await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn get_object_writer_test() {
+    let config = S3ClientConfig::builder().endpoint("http://127.0.0.1:9000").access_key("minioadmin").secret_key("minioadmin").build().unwrap();
+    let client = S3Client::new(config);
+
+    let result = client.bucket("test").put_object("writer.txt").from_bytes("writer").await;
+    assert!(result.is_ok());
+
+    let mut buffer = Vec::new();
+    let result = client.bucket("test").get_object("writer.txt").to_writer(&mut buffer).await;
+    assert_eq!(result.unwrap(), 6);
+    assert_eq!(buffer, b"writer");
+
+    let result = client.bucket("test").delete_object("writer.txt").await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn put_object_checksum_test() {
+    let config = S3ClientConfig::builder().endpoint("http://127.0.0.1:9000").access_key("minioadmin").secret_key("minioadmin").build().unwrap();
+    let client = S3Client::new(config);
+
+    let result = client.bucket("test").put_object("checksum.txt").from_bytes_with_checksum("hello world", ChecksumAlgorithm::Sha256).await;
+    assert_eq!(result.unwrap(), "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9");
+
+    let result = client.bucket("test").put_object("checksum.txt").from_stream_with_checksum(ByteStream::from("hello world"), ChecksumAlgorithm::Md5).await;
+    assert_eq!(result.unwrap(), "5eb63bbbe01eeed093cb22bb8f5acdc3");
+
+    tokio::fs::write("/tmp/s3_checksum.txt", "hello world").await.unwrap();
+    let result = client.bucket("test").put_object("checksum.txt").from_path_with_checksum("/tmp/s3_checksum.txt", ChecksumAlgorithm::Sha256).await;
+    assert_eq!(result.unwrap(), "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9");
+    tokio::fs::remove_file("/tmp/s3_checksum.txt").await.unwrap();
+
+    let result = client.bucket("test").delete_object("checksum.txt").await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn delete_objects_test() {
+    let config = S3ClientConfig::builder().endpoint("http://127.0.0.1:9000").access_key("minioadmin").secret_key("minioadmin").build().unwrap();
+    let client = S3Client::new(config);
+    let bucket = client.bucket("test");
+
+    let keys: Vec<String> = (0..5).map(|index| format!("bulk/{}.txt", index)).collect();
+    for key in &keys {
+        assert!(bucket.put_object(key).from_bytes("bulk").await.is_ok());
+    }
+
+    let result = bucket.delete_objects(keys.clone()).await.unwrap();
+    assert_eq!(result.deleted.len(), keys.len());
+    assert!(result.failed.is_empty());
+    assert!(bucket.list_objects(Some("bulk/"), None).await.unwrap().is_empty());
+
+    let result = bucket.delete_objects(Vec::new()).await.unwrap();
+    assert!(result.deleted.is_empty());
+}
+
+#[tokio::test]
+async fn head_object_test() {
+    let config = S3ClientConfig::builder().endpoint("http://127.0.0.1:9000").access_key("minioadmin").secret_key("minioadmin").build().unwrap();
+    let client = S3Client::new(config);
+    let bucket = client.bucket("test");
+
+    assert!(bucket.put_object("head/exists.txt").content_type("text/plain").from_bytes("hello").await.is_ok());
+
+    let metadata = bucket.head_object("head/exists.txt").await.unwrap().unwrap();
+    assert_eq!(metadata.size, 5);
+    assert_eq!(metadata.content_type.as_deref(), Some("text/plain"));
+    assert!(metadata.last_modified.is_some());
+    assert!(metadata.etag.is_some());
+
+    let metadata = bucket.head_object("head/missing.txt").await.unwrap();
+    assert!(metadata.is_none());
+
+    assert!(bucket.delete_object("head/exists.txt").await.is_ok());
+}
+
+#[tokio::test]
+async fn copy_object_test() {
+    let config = S3ClientConfig::builder().endpoint("http://127.0.0.1:9000").access_key("minioadmin").secret_key("minioadmin").build().unwrap();
+    let client = S3Client::new(config);
+    let bucket = client.bucket("test");
+
+    assert!(bucket.put_object("copy/source file.txt").from_bytes("copy").await.is_ok());
+
+    assert!(bucket.copy_object("copy/source file.txt", "copy/copied.txt").await.is_ok());
+    assert!(bucket.head_object("copy/source file.txt").await.unwrap().is_some());
+    assert_eq!(bucket.get_object("copy/copied.txt").as_bytes().await.unwrap(), "copy");
+
+    assert!(bucket.move_object("copy/copied.txt", "copy/copied.txt").await.is_err());
+    assert!(bucket.move_object("copy/copied.txt", "copy/moved.txt").await.is_ok());
+    assert!(bucket.head_object("copy/copied.txt").await.unwrap().is_none());
+    assert_eq!(bucket.get_object("copy/moved.txt").as_bytes().await.unwrap(), "copy");
+
+    assert!(bucket.delete_prefix("copy/").await.is_ok());
+}
+
+#[test]
+fn client_config_resilience_test() {
+    use std::time::Duration;
+
+    let config = S3ClientConfig::builder().endpoint("http://127.0.0.1:9000").max_retries(5).operation_timeout(Duration::from_secs(30)).connect_timeout(Duration::from_secs(2)).build().unwrap();
+    let client = S3Client::build_client(config);
+    assert_eq!(client.config().retry_config().unwrap().max_attempts(), 6);
+    assert_eq!(client.config().timeout_config().unwrap().operation_timeout(), Some(Duration::from_secs(30)));
+    assert_eq!(client.config().timeout_config().unwrap().connect_timeout(), Some(Duration::from_secs(2)));
+
+    let config = S3ClientConfig::builder().endpoint("http://127.0.0.1:9000").build().unwrap();
+    let client = S3Client::build_client(config);
+    assert!(client.config().retry_config().is_none());
+    assert!(client.config().timeout_config().is_none());
+
+    let config = S3ClientConfig::builder().endpoint("http://127.0.0.1:9000").operation_timeout(Duration::from_secs(30)).build().unwrap();
+    let client = S3Client::build_client(config);
+    assert!(client.config().timeout_config().unwrap().has_timeouts());
+    assert_eq!(client.config().timeout_config().unwrap().connect_timeout(), None);
+}
+
+#[tokio::test]
+async fn path_style_test() {
+    use std::time::Duration;
+
+    let config = S3ClientConfig::builder().endpoint("http://127.0.0.1:9000").access_key("minioadmin").secret_key("minioadmin").build().unwrap();
+    let url = S3Client::new(config).bucket("test").presign_get("test.txt", Duration::from_secs(60)).await.unwrap();
+    assert!(url.starts_with("http://127.0.0.1:9000/test/test.txt"));
+
+    let config = S3ClientConfig::builder().endpoint("https://s3.eu-north-1.amazonaws.com").region("eu-north-1").build().unwrap();
+    let url = S3Client::new(config).bucket("test").presign_get("test.txt", Duration::from_secs(60)).await.unwrap();
+    assert!(url.starts_with("https://test.s3.eu-north-1.amazonaws.com/test.txt"));
+
+    let config = S3ClientConfig::builder().endpoint("https://s3.eu-north-1.amazonaws.com").region("eu-north-1").force_path_style(true).build().unwrap();
+    let client = S3Client::new(config);
+    let url = client.bucket("test").presign_get("test.txt", Duration::from_secs(60)).await.unwrap();
+    assert!(url.starts_with("https://s3.eu-north-1.amazonaws.com/test/test.txt"));
+
+    let config = S3ClientConfig::builder().endpoint("http://localhost:9000").access_key("minioadmin").secret_key("minioadmin").force_path_style(false).build().unwrap();
+    let client = S3Client::new(config);
+    let url = client.bucket("test").presign_get("test.txt", Duration::from_secs(60)).await.unwrap();
+    assert!(url.starts_with("http://test.localhost:9000/test.txt"));
+    assert!(client.bucket("test").presign_post("uploads/", Default::default(), Duration::from_secs(60)).unwrap().url.starts_with("http://test.localhost:9000"));
+
+    let config = S3ClientConfig::builder().endpoint("http://localhost:9000").access_key("minioadmin").secret_key("minioadmin").build().unwrap();
+    let bucket = S3Client::new(config).bucket("test");
+    assert!(bucket.put_object("path_style.txt").from_bytes("path").await.is_ok());
+    assert!(bucket.delete_object("path_style.txt").await.is_ok());
+}
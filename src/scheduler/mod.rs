@@ -2,6 +2,8 @@
 pub mod scheduler;
 #[cfg(feature = "scheduler")]
 pub mod scheduler_config;
+#[cfg(feature = "scheduler")]
+pub mod scheduler_manager;
 
 #[cfg(feature = "scheduler")]
 #[cfg(test)]
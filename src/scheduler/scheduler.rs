@@ -1,27 +1,38 @@
 use std::{panic::AssertUnwindSafe, pin::Pin, sync::Arc, time::Duration};
 
 use futures::FutureExt;
-use time::{OffsetDateTime};
-use tokio::{signal::unix::{signal, SignalKind}, task::JoinSet, time::sleep};
+use time::{OffsetDateTime, PrimitiveDateTime};
+use time_tz::{Offset, OffsetDateTimeExt, OffsetResult, PrimitiveDateTimeExt, TimeZone, Tz};
+use tokio::{signal::unix::{signal, SignalKind}, sync::{Notify, watch}, task::JoinSet, time::sleep};
 
-use crate::scheduler::scheduler_config::SchedulerConfig;
+use crate::scheduler::scheduler_config::{SchedulerConfig, Weekday};
 
 type TriggerCallback = Arc<dyn Fn() -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
 
 pub struct Scheduler {
     config: SchedulerConfig,
-    next_run: OffsetDateTime,
+    next_run: PrimitiveDateTime,
     callback: TriggerCallback,
+    shutdown: Arc<Notify>,
+    stopped: watch::Sender<bool>,
 }
 
 impl Scheduler {
     pub fn new(config: SchedulerConfig) -> Self {
-        let start_date = config.start_date;
-        let start_time = config.start_time;
+        let now = Self::to_wall_clock(OffsetDateTime::now_utc(), config.timezone);
+        let start_date = config.start_date.unwrap_or(now.date());
+        let start_time = config.start_time.unwrap_or(now.time());
+        let mut config = config;
+        if !config.days_of_week.is_empty() && config.interval.is_none() {
+            config.interval = Some(Duration::from_secs(24 * 60 * 60));
+        }
+
         Scheduler {
             config,
-            next_run: start_date.with_time(start_time).assume_utc(),
+            next_run: start_date.with_time(start_time),
             callback: Arc::new(|| Box::pin(async {})),
+            shutdown: Arc::new(Notify::new()),
+            stopped: watch::channel(false).0,
         }
     }
 
@@ -34,42 +45,76 @@ impl Scheduler {
         self
     }
 
-    pub async fn run(mut self) {
+    /// Returns a handle for stopping the scheduler from another task.
+    pub fn shutdown_handle(&self) -> SchedulerShutdown {
+        SchedulerShutdown {
+            shutdown: self.shutdown.clone(),
+            stopped: self.stopped.subscribe(),
+        }
+    }
+
+    /// Runs the scheduler until the schedule ends, a system termination signal (SIGINT, SIGTERM) or the
+    /// [`shutdown_handle`](Scheduler::shutdown_handle) aborts it.
+    pub async fn run(self) {
+        let shutdown = self.shutdown.clone();
+        let stopped = self.stopped.clone();
         let mut receiver_join_set = JoinSet::new();
-        let mut sigterm = signal(SignalKind::terminate()).expect("Failed to start SIGTERM signal receiver");
-        let mut sigint = signal(SignalKind::interrupt()).expect("Failed to start SIGINT signal receiver");
+        receiver_join_set.spawn(self.job());
+        Self::wait(receiver_join_set, &shutdown).await;
+        stopped.send_replace(true);
+    }
 
-        if self.next_run < OffsetDateTime::now_utc() {
-            self.next_run = Self::calculate_next_run(self.next_run, self.config.interval).await;
+    /// Runs the task at the scheduled times until the schedule ends.
+    pub(crate) async fn job(mut self) {
+        let timezone = self.config.timezone;
+        if Self::resolve(self.next_run, timezone) < OffsetDateTime::now_utc() || !Self::is_scheduled_day(self.next_run, &self.config.days_of_week) {
+            self.next_run = match Self::calculate_next_run(self.next_run, self.config.interval, &self.config.days_of_week, timezone).await {
+                Some(next_run) => next_run,
+                None => {
+                    tracing::error!("Scheduler interval never falls on the configured days of week");
+                    return;
+                }
+            };
         }
 
         tracing::trace!("Scheduler next run at {:?}", self.next_run);
-        receiver_join_set.spawn(async move {
-            loop {
-                let now = OffsetDateTime::now_utc();
-                if self.next_run > now {
-                    let duration = Self::to_std_duration(self.next_run - now);
-                    tracing::trace!("Sleep: {:?}", duration);
-                    sleep(duration).await;
-                }
-                
-                if self.config.interval != None {
-                    self.next_run = Self::calculate_next_run(self.next_run, self.config.interval).await;
-                }
+        loop {
+            let now = OffsetDateTime::now_utc();
+            let next_run_at = Self::resolve(self.next_run, timezone);
+            if next_run_at > now {
+                let duration = Self::to_std_duration(next_run_at - now);
+                tracing::trace!("Sleep: {:?}", duration);
+                sleep(duration).await;
+            }
+            
+            let next_run = match self.config.interval {
+                Some(_) => Self::calculate_next_run(self.next_run, self.config.interval, &self.config.days_of_week, timezone).await,
+                None => Some(self.next_run),
+            };
+
+            let callback_fut = (self.callback)();
+            let result = AssertUnwindSafe(callback_fut).catch_unwind().await;
+            if let Err(err) = result {
+                tracing::trace!("{:?}", err);
+            }
+            
+            if self.config.interval == None {
+                break;
+            }
 
-                let callback_fut = (self.callback)();
-                let result = AssertUnwindSafe(callback_fut).catch_unwind().await;
-                if let Err(err) = result {
-                    tracing::trace!("{:?}", err);
-                }
-                
-                if self.config.interval == None {
-                    break;
-                }
+            self.next_run = match next_run {
+                Some(next_run) => next_run,
+                None => break,
+            };
 
-                tracing::trace!("Scheduler next run at {:?}", self.next_run);
-            }
-        });
+            tracing::trace!("Scheduler next run at {:?}", self.next_run);
+        }
+    }
+
+    /// Waits for all jobs to finish, aborting them on system termination signals (SIGINT, SIGTERM) or shutdown.
+    pub(crate) async fn wait(mut receiver_join_set: JoinSet<()>, shutdown: &Notify) {
+        let mut sigterm = signal(SignalKind::terminate()).expect("Failed to start SIGTERM signal receiver");
+        let mut sigint = signal(SignalKind::interrupt()).expect("Failed to start SIGINT signal receiver");
 
         loop {
             tokio::select! {
@@ -81,6 +126,10 @@ impl Scheduler {
                     receiver_join_set.abort_all();
                     break;
                 },
+                _ = shutdown.notified() => {
+                    receiver_join_set.abort_all();
+                    break;
+                },
                 task = receiver_join_set.join_next() => {
                     if task.is_none() {
                         break;
@@ -90,18 +139,67 @@ impl Scheduler {
         }
     }
 
-    async fn calculate_next_run(next_run: OffsetDateTime, interval: Option<Duration>) -> OffsetDateTime {
+    /// Calculates the next run as a wall-clock time in the time zone, or `UTC` if none is set.
+    pub(crate) async fn calculate_next_run(next_run: PrimitiveDateTime, interval: Option<Duration>, days_of_week: &[Weekday], timezone: Option<&Tz>) -> Option<PrimitiveDateTime> {
         
         if let Some(duration) = interval {
             let now = OffsetDateTime::now_utc();
             let mut calculated_next_run = next_run;
-            while calculated_next_run < now {
-                calculated_next_run += duration;
+            while Self::resolve(calculated_next_run, timezone) < now {
+                calculated_next_run = Self::step(calculated_next_run, duration, timezone);
+            }
+
+            // Every weekday is reached within a few weeks unless the interval is aligned to whole weeks.
+            let search_limit = calculated_next_run + time::Duration::weeks(8);
+            while !Self::is_scheduled_day(calculated_next_run, days_of_week) {
+                calculated_next_run = Self::step(calculated_next_run, duration, timezone);
+                if calculated_next_run > search_limit {
+                    return None;
+                }
             }
-            return calculated_next_run;
+            return Some(calculated_next_run);
+        }
+
+        Some(next_run)
+    }
+
+    /// Advances a wall-clock time by the interval, whole days follow the wall-clock and shorter intervals absolute time.
+    pub(crate) fn step(date_time: PrimitiveDateTime, interval: Duration, timezone: Option<&Tz>) -> PrimitiveDateTime {
+        if interval.as_secs().is_multiple_of(24 * 60 * 60) && interval.subsec_nanos() == 0 {
+            return date_time + interval;
+        }
+
+        Self::to_wall_clock(Self::resolve(date_time, timezone) + interval, timezone)
+    }
+
+    /// Resolves a wall-clock time in the time zone to an absolute time.
+    pub(crate) fn resolve(date_time: PrimitiveDateTime, timezone: Option<&Tz>) -> OffsetDateTime {
+        let timezone = match timezone {
+            Some(timezone) => timezone,
+            None => return date_time.assume_utc(),
+        };
+
+        match date_time.assume_timezone(timezone) {
+            OffsetResult::Some(resolved) => resolved,
+            OffsetResult::Ambiguous(first, _) => first,
+            OffsetResult::None => {
+                // Skipped by a daylight saving gap, using the offset before the gap moves it forward by the gap length.
+                let before = date_time.assume_utc() - time::Duration::days(1);
+                date_time.assume_offset(timezone.get_offset_utc(&before).to_utc())
+            },
         }
+    }
+
+    fn to_wall_clock(date_time: OffsetDateTime, timezone: Option<&Tz>) -> PrimitiveDateTime {
+        let date_time = match timezone {
+            Some(timezone) => date_time.to_timezone(timezone),
+            None => date_time.to_offset(time::UtcOffset::UTC),
+        };
+        PrimitiveDateTime::new(date_time.date(), date_time.time())
+    }
 
-        next_run
+    fn is_scheduled_day(date_time: PrimitiveDateTime, days_of_week: &[Weekday]) -> bool {
+        days_of_week.is_empty() || days_of_week.contains(&date_time.weekday())
     }
 
     fn to_std_duration(time_duration: time::Duration) -> Duration {
@@ -113,4 +211,22 @@ impl Default for Scheduler {
     fn default() -> Self {
         Scheduler::new(SchedulerConfig::new())
     }
-}
\ No newline at end of file
+}
+
+/// Cloneable handle for shutting down a running [`Scheduler`] or [`SchedulerManager`](crate::scheduler::scheduler_manager::SchedulerManager).
+#[derive(Clone)]
+pub struct SchedulerShutdown {
+    pub(crate) shutdown: Arc<Notify>,
+    pub(crate) stopped: watch::Receiver<bool>,
+}
+
+impl SchedulerShutdown {
+    /// Aborts the scheduled jobs, including a running trigger, and waits until the scheduler has stopped.
+    /// 
+    /// Returns immediately if the scheduler has already stopped or was dropped without running.
+    pub async fn shutdown(&self) {
+        self.shutdown.notify_one();
+        let mut stopped = self.stopped.clone();
+        let _ = stopped.wait_for(|stopped| *stopped).await;
+    }
+}
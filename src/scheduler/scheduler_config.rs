@@ -1,19 +1,29 @@
 use std::time::Duration;
 
-use time::{Date, OffsetDateTime, Time};
+use time::{Date, Time};
+use time_tz::Tz;
 
+pub use time::Weekday;
+
+/// Configuration of when a [`Scheduler`](crate::scheduler::scheduler::Scheduler) runs its task.
 pub struct SchedulerConfig {
     pub interval: Option<Duration>,
-    pub start_date: Date,
-    pub start_time: Time,
+    pub days_of_week: Vec<Weekday>,
+    /// The start date, or `None` for the current date in the configured time zone when the scheduler starts.
+    pub start_date: Option<Date>,
+    /// The start time, or `None` for the current time in the configured time zone when the scheduler starts.
+    pub start_time: Option<Time>,
+    pub timezone: Option<&'static Tz>,
 }
 
 impl SchedulerConfig {
     pub fn new() -> Self {
         SchedulerConfig {
             interval: None,
-            start_date: OffsetDateTime::now_utc().date(),
-            start_time: OffsetDateTime::now_utc().time(),
+            days_of_week: Vec::new(),
+            start_date: None,
+            start_time: None,
+            timezone: None,
         }
     }
 
@@ -23,26 +33,51 @@ impl SchedulerConfig {
         self
     }
 
-    /// Sets the `UTC` start date for the scheduled task.
+    /// Restricts the scheduled runs to the given days of the week, e.g. `&[Weekday::Monday, Weekday::Thursday]`.
+    /// 
+    /// Runs falling on other days are skipped by advancing with the interval until a matching day is reached.
+    /// Combine with a daily interval and a start time to run at a fixed time on selected days, if no interval is set a daily interval is used.
+    pub fn days_of_week(mut self, days: &[Weekday]) -> Self {
+        self.days_of_week = days.to_vec();
+        self
+    }
+
+    /// Sets the start date for the scheduled task, defaults to the current date.
     /// 
     /// If the provided date is in the past, the scheduler will calculate the next valid future run based on the defined interval.
     /// 
-    /// Note: Scheduler is using `UTC: Coordinated Universal Time` to avoid daylight saving problems.
+    /// Note: The date is in `UTC: Coordinated Universal Time` unless a [`timezone`](SchedulerConfig::timezone) is set.
     pub fn start_date(mut self, year: i32, month: u8, day: u8) -> Self {
-        //let time = self.start_datetime.time();
-        //self.start_date = date.with_time(time).assume_utc();
-        self.start_date = Date::from_calendar_date(year, month.try_into().unwrap(), day).expect("Not a valid date.");
+        self.start_date = Some(Date::from_calendar_date(year, month.try_into().unwrap(), day).expect("Not a valid date."));
         self
     }
     
-    /// Sets the `UTC` start time for the scheduled task.
+    /// Sets the start time for the scheduled task, defaults to the current time.
     /// 
     /// If the provided time is in the past, the scheduler will calculate the next valid future run based on the defined interval.
     /// 
-    /// Note: Scheduler is using `UTC: Coordinated Universal Time` to avoid daylight saving problems.
+    /// Note: The time is in `UTC: Coordinated Universal Time` unless a [`timezone`](SchedulerConfig::timezone) is set.
     pub fn start_time(mut self, hour: u8, minute: u8, second: u8) -> Self {
-        //let date = self.start_datetime.date();
-        self.start_time = Time::from_hms(hour, minute, second).expect("Not a valid time.");
+        self.start_time = Some(Time::from_hms(hour, minute, second).expect("Not a valid time."));
         self
     }
+
+    /// Sets an IANA time zone, e.g. `Europe/Stockholm`, used for the start date, start time and days of week.
+    /// 
+    /// Intervals of whole days follow the local wall-clock time, so a daily task at 09:00 stays at 09:00 across
+    /// daylight saving changes, while shorter intervals advance in absolute time.
+    /// A start time skipped by a daylight saving change is moved forward by the length of the gap,
+    /// and a start time occurring twice uses the first occurrence.
+    /// 
+    /// Panics if the name is not a known time zone, see [`try_timezone`](SchedulerConfig::try_timezone) for names from user input.
+    pub fn timezone(self, name: impl AsRef<str>) -> Self {
+        self.try_timezone(name).expect("Not a valid time zone.")
+    }
+
+    /// Sets an IANA time zone like [`timezone`](SchedulerConfig::timezone), returning an error if the name is not a known time zone.
+    pub fn try_timezone(mut self, name: impl AsRef<str>) -> anyhow::Result<Self> {
+        let name = name.as_ref();
+        self.timezone = Some(time_tz::timezones::get_by_name(name).ok_or_else(|| anyhow::anyhow!("Not a valid time zone: {}", name))?);
+        Ok(self)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,62 @@
+use std::sync::Arc;
+
+use tokio::{sync::{Notify, watch}, task::JoinSet};
+use tracing::Instrument;
+
+use crate::scheduler::{scheduler::{Scheduler, SchedulerShutdown}, scheduler_config::SchedulerConfig};
+
+/// Runs many named scheduled jobs sharing a single signal handler.
+/// 
+/// Each job keeps its own schedule and logs are tagged with the job name.
+pub struct SchedulerManager {
+    jobs: Vec<(String, Scheduler)>,
+    shutdown: Arc<Notify>,
+    stopped: watch::Sender<bool>,
+}
+
+impl SchedulerManager {
+    pub fn new() -> Self {
+        SchedulerManager {
+            jobs: Vec::new(),
+            shutdown: Arc::new(Notify::new()),
+            stopped: watch::channel(false).0,
+        }
+    }
+
+    /// Adds a named job triggering the callback according to the config.
+    pub fn add_job<T, Fut>(mut self, name: impl Into<String>, config: SchedulerConfig, callback: T) -> Self
+    where
+        T: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.jobs.push((name.into(), Scheduler::new(config).trigger(callback)));
+        self
+    }
+
+    /// Returns a handle for stopping all jobs from another task.
+    pub fn shutdown_handle(&self) -> SchedulerShutdown {
+        SchedulerShutdown {
+            shutdown: self.shutdown.clone(),
+            stopped: self.stopped.subscribe(),
+        }
+    }
+
+    /// Runs all jobs until every schedule has ended or a system termination signal (SIGINT, SIGTERM) or the
+    /// [`shutdown_handle`](SchedulerManager::shutdown_handle) aborts them.
+    pub async fn run(self) {
+        let mut receiver_join_set = JoinSet::new();
+        for (name, scheduler) in self.jobs {
+            let span = tracing::info_span!("job", name = %name);
+            receiver_join_set.spawn(scheduler.job().instrument(span));
+        }
+
+        Scheduler::wait(receiver_join_set, &self.shutdown).await;
+        self.stopped.send_replace(true);
+    }
+}
+
+impl Default for SchedulerManager {
+    fn default() -> Self {
+        SchedulerManager::new()
+    }
+}
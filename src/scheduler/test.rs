@@ -1,4 +1,104 @@
 #[tokio::test]
 async fn client_test() {
     tracing_subscriber::fmt().with_max_level(tracing::Level::INFO).init();
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn days_of_week_test() {
+    use std::time::Duration;
+    use time::{Date, Month, OffsetDateTime, Time};
+    use crate::scheduler::{scheduler::Scheduler, scheduler_config::Weekday};
+
+    let now = OffsetDateTime::now_utc();
+    let start = Date::from_calendar_date(now.year() + 1, Month::January, 1).unwrap().with_time(Time::from_hms(9, 0, 0).unwrap());
+    let day = Duration::from_secs(24 * 60 * 60);
+
+    let next_run = Scheduler::calculate_next_run(start, Some(day), &[Weekday::Monday, Weekday::Thursday], None).await.unwrap();
+    assert!(matches!(next_run.weekday(), Weekday::Monday | Weekday::Thursday));
+    assert_eq!(next_run.time(), Time::from_hms(9, 0, 0).unwrap());
+    assert!(next_run - start < time::Duration::days(4));
+
+    let week = Duration::from_secs(7 * 24 * 60 * 60);
+    let weekday = start.weekday().next();
+    assert!(Scheduler::calculate_next_run(start, Some(week), &[weekday], None).await.is_none());
+}
+
+#[test]
+fn timezone_test() {
+    use std::time::Duration;
+    use time::{Date, Month, Time, UtcOffset};
+    use crate::scheduler::{scheduler::Scheduler, scheduler_config::SchedulerConfig};
+
+    let timezone = time_tz::timezones::get_by_name("Europe/Stockholm");
+    let day = Duration::from_secs(24 * 60 * 60);
+
+    // Daylight saving starts 2030-03-31 at 02:00 in Stockholm.
+    let start = Date::from_calendar_date(2030, Month::March, 30).unwrap().with_time(Time::from_hms(9, 0, 0).unwrap());
+    let next = Scheduler::step(start, day, timezone);
+    assert_eq!(next.time(), Time::from_hms(9, 0, 0).unwrap());
+    assert_eq!(Scheduler::resolve(start, timezone).offset(), UtcOffset::from_hms(1, 0, 0).unwrap());
+    assert_eq!(Scheduler::resolve(next, timezone).offset(), UtcOffset::from_hms(2, 0, 0).unwrap());
+    assert_eq!(Scheduler::resolve(next, timezone) - Scheduler::resolve(start, timezone), time::Duration::hours(23));
+
+    let gap = Date::from_calendar_date(2030, Month::March, 31).unwrap().with_time(Time::from_hms(2, 30, 0).unwrap());
+    let resolved = Scheduler::resolve(gap, timezone);
+    assert_eq!(resolved.to_offset(UtcOffset::from_hms(2, 0, 0).unwrap()).time(), Time::from_hms(3, 30, 0).unwrap());
+
+    let hour = Duration::from_secs(60 * 60);
+    let before = Date::from_calendar_date(2030, Month::March, 31).unwrap().with_time(Time::from_hms(1, 0, 0).unwrap());
+    assert_eq!(Scheduler::step(before, hour, timezone).time(), Time::from_hms(3, 0, 0).unwrap());
+
+    assert!(SchedulerConfig::new().try_timezone("Europe/Stockholm").unwrap().timezone.is_some());
+    assert!(SchedulerConfig::new().try_timezone("Europe/Nowhere").is_err());
+}
+
+#[tokio::test]
+async fn manager_test() {
+    use std::{sync::{Arc, atomic::{AtomicU32, Ordering}}, time::Duration};
+    use crate::scheduler::{scheduler_config::SchedulerConfig, scheduler_manager::SchedulerManager};
+
+    let first = Arc::new(AtomicU32::new(0));
+    let second = Arc::new(AtomicU32::new(0));
+    let first_count = first.clone();
+    let second_count = second.clone();
+
+    let manager = SchedulerManager::new()
+    .add_job("first", SchedulerConfig::new(), move || {
+        let count = first_count.clone();
+        async move {
+            count.fetch_add(1, Ordering::SeqCst);
+        }
+    })
+    .add_job("second", SchedulerConfig::new().interval(Duration::from_millis(100)), move || {
+        let count = second_count.clone();
+        async move {
+            count.fetch_add(1, Ordering::SeqCst);
+        }
+    });
+
+    let result = tokio::time::timeout(Duration::from_millis(450), manager.run()).await;
+    assert!(result.is_err());
+    assert_eq!(first.load(Ordering::SeqCst), 1);
+    assert!(second.load(Ordering::SeqCst) >= 3);
+}
+
+#[tokio::test]
+async fn shutdown_test() {
+    use std::time::Duration;
+    use crate::scheduler::{scheduler::Scheduler, scheduler_config::SchedulerConfig, scheduler_manager::SchedulerManager};
+
+    let scheduler = Scheduler::new(SchedulerConfig::new().interval(Duration::from_secs(60 * 60))).trigger(|| async {});
+    let handle = scheduler.shutdown_handle();
+    let task = tokio::spawn(scheduler.run());
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    tokio::time::timeout(Duration::from_secs(1), handle.shutdown()).await.unwrap();
+    tokio::time::timeout(Duration::from_secs(1), task).await.unwrap().unwrap();
+
+    let manager = SchedulerManager::new()
+    .add_job("first", SchedulerConfig::new().interval(Duration::from_secs(60 * 60)), || async {})
+    .add_job("second", SchedulerConfig::new().interval(Duration::from_secs(60 * 60)), || async {});
+    let handle = manager.shutdown_handle();
+    let task = tokio::spawn(manager.run());
+    tokio::time::timeout(Duration::from_secs(1), handle.shutdown()).await.unwrap();
+    tokio::time::timeout(Duration::from_secs(1), task).await.unwrap().unwrap();
+}
@@ -8,6 +8,8 @@ mod ssh_client;
 pub mod sftp_client;
 #[cfg(feature = "sftp")]
 pub mod sftp_client_config;
+#[cfg(feature = "sftp")]
+pub mod sftp_file_entry;
 
 #[cfg(feature = "sftp")]
 #[cfg(test)]
@@ -1,40 +1,116 @@
-use std::{marker::PhantomData, path::{Path, PathBuf}, sync::Arc};
+use std::{collections::HashSet, io::ErrorKind, marker::PhantomData, path::{Path, PathBuf}, sync::{Arc, atomic::{AtomicU64, Ordering}}, time::Duration};
 
 use anyhow::Ok;
 use bytes::Bytes;
+use futures::StreamExt;
+use regex::Regex;
 use russh::{client::Handle, keys::{HashAlg, PrivateKeyWithHashAlg}};
-use russh_sftp::client::SftpSession;
-use tokio::{io::{AsyncReadExt, AsyncWriteExt}, sync::Mutex};
+use russh_sftp::{client::{RawSftpSession, SftpSession, error::Error as SftpError}, protocol::{Packet, StatusCode}};
+use tokio::{io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt}, sync::{Mutex, OnceCell}};
 use tokio_util::io::ReaderStream;
 
-use crate::{common::stream::ByteStream, sftp::{sftp_client_config::SftpClientConfig, ssh_client::SshClient}};
+use crate::{common::{stream::ByteStream, utils}, sftp::{sftp_client_config::SftpClientConfig, sftp_file_entry::SftpFileEntry, ssh_client::SshClient}};
+
+type ProgressCallback = Arc<dyn Fn(u64, Option<u64>) + Send + Sync>;
+
+/// The default size of the buffer used for each read and write during transfers.
+pub const DEFAULT_BUFFER_SIZE: usize = 32 * 1024;
+
+/// The longest delay between retries.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// The OpenSSH extension that renames over an existing file in one step.
+const POSIX_RENAME: &str = "posix-rename@openssh.com";
 
 pub struct Empty;
 pub struct GetFile;
 pub struct PutFile;
+pub struct GetDir;
+
+/// The SSH session and SFTP subsystem shared by a client and all clients derived from it.
+struct SftpConnection {
+    ssh: Handle<SshClient>,
+    sftp: Arc<SftpSession>,
+    /// A raw SFTP channel for [`POSIX_RENAME`], opened on first use and `None` when the server does not offer it.
+    posix_rename: OnceCell<Option<Arc<RawSftpSession>>>,
+}
 
 pub struct SftpClient<State> {
-    config: Arc<SftpClientConfig>,
+    config: Option<Arc<SftpClientConfig>>,
     path: Option<PathBuf>,
-    session: Arc<Mutex<Option<Handle<SshClient>>>>,
+    session: Arc<Mutex<Option<SftpConnection>>>,
+    sftp: Option<Arc<SftpSession>>,
+    bytes_sent: Arc<AtomicU64>,
+    bytes_received: Arc<AtomicU64>,
+    recursive: bool,
+    regex: Option<Regex>,
+    delete_after_download: bool,
+    on_progress: Option<ProgressCallback>,
+    buffer_size: usize,
+    atomic: bool,
     _state: PhantomData<State>,
 }
 
 impl SftpClient<Empty> {
     pub fn new(config: SftpClientConfig) -> Self {
         SftpClient {
-            config: Arc::new(config),
+            config: Some(Arc::new(config)),
             path: None,
             session: Arc::new(Mutex::new(None)),
+            sftp: None,
+            bytes_sent: Arc::new(AtomicU64::new(0)),
+            bytes_received: Arc::new(AtomicU64::new(0)),
+            recursive: false,
+            regex: None,
+            delete_after_download: false,
+            on_progress: None,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            atomic: false,
             _state: PhantomData
         }
     }
 
+    /// Creates a client over an already authenticated SSH session by opening the SFTP subsystem on a new channel.
+    /// 
+    /// This avoids a second handshake and authentication when an SSH connection is already in hand.
+    /// The session is owned by the caller and is not reconnected if it closes.
+    pub async fn from_session<H: russh::client::Handler>(session: &Handle<H>) -> anyhow::Result<Self> {
+        tracing::trace!("SSH requesting SFTP subsystem on existing session");
+        let channel = session.channel_open_session().await?;
+        channel.request_subsystem(true, "sftp").await?;
+        let sftp = SftpSession::new(channel.into_stream()).await?;
+
+        Ok(SftpClient {
+            config: None,
+            path: None,
+            session: Arc::new(Mutex::new(None)),
+            sftp: Some(Arc::new(sftp)),
+            bytes_sent: Arc::new(AtomicU64::new(0)),
+            bytes_received: Arc::new(AtomicU64::new(0)),
+            recursive: false,
+            regex: None,
+            delete_after_download: false,
+            on_progress: None,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            atomic: false,
+            _state: PhantomData
+        })
+    }
+
     pub fn get_file(&self, path: impl Into<PathBuf>) -> SftpClient<GetFile> {
         SftpClient {
             config: self.config.clone(),
             path: Some(path.into()),
             session: self.session.clone(),
+            sftp: self.sftp.clone(),
+            bytes_sent: self.bytes_sent.clone(),
+            bytes_received: self.bytes_received.clone(),
+            recursive: false,
+            regex: None,
+            delete_after_download: false,
+            on_progress: self.on_progress.clone(),
+            buffer_size: self.buffer_size,
+            atomic: false,
             _state: PhantomData
         }
     }
@@ -44,6 +120,34 @@ impl SftpClient<Empty> {
             config: self.config.clone(),
             path: Some(path.into()),
             session: self.session.clone(),
+            sftp: self.sftp.clone(),
+            bytes_sent: self.bytes_sent.clone(),
+            bytes_received: self.bytes_received.clone(),
+            recursive: false,
+            regex: None,
+            delete_after_download: false,
+            on_progress: self.on_progress.clone(),
+            buffer_size: self.buffer_size,
+            atomic: false,
+            _state: PhantomData
+        }
+    }
+
+    /// Downloads the files in a remote directory, see [`to_path`](SftpClient::to_path).
+    pub fn get_dir(&self, path: impl Into<PathBuf>) -> SftpClient<GetDir> {
+        SftpClient {
+            config: self.config.clone(),
+            path: Some(path.into()),
+            session: self.session.clone(),
+            sftp: self.sftp.clone(),
+            bytes_sent: self.bytes_sent.clone(),
+            bytes_received: self.bytes_received.clone(),
+            recursive: false,
+            regex: None,
+            delete_after_download: false,
+            on_progress: self.on_progress.clone(),
+            buffer_size: self.buffer_size,
+            atomic: false,
             _state: PhantomData
         }
     }
@@ -61,81 +165,551 @@ impl SftpClient<Empty> {
 
 impl SftpClient<GetFile> {
     pub async fn as_bytes(&mut self) -> anyhow::Result<Bytes> {
-        let session = self.get_session().await?;
-        let path = self.path.as_ref().unwrap().to_string_lossy();
+        self.retry(async |client: &Self, session: Arc<SftpSession>| {
+            let path = client.path.as_ref().unwrap().to_string_lossy();
 
-        let mut remote_file = session.open(path).await?;
-        let mut buffer = Vec::new();
-        remote_file.read_to_end(&mut buffer).await?;
-        remote_file.shutdown().await?;
+            let mut remote_file = session.open(path).await?;
+            let total = remote_file.metadata().await?.size;
+            let mut buffer = Vec::new();
+            client.copy(&mut remote_file, &mut buffer, total, &client.bytes_received).await?;
+            remote_file.shutdown().await?;
 
-        Ok(Bytes::from(buffer))
+            Ok(Bytes::from(buffer))
+        }).await
     }
 
+    /// Returns the file as a stream with the content-length of the file, if known.
+    /// 
+    /// Bytes are added to [`bytes_received`](SftpClient::bytes_received) as the stream is consumed.
     pub async fn as_stream(&mut self) -> anyhow::Result<ByteStream> {
         let session = self.get_session().await?;
         let path = self.path.as_ref().unwrap().to_string_lossy();
 
         let remote_file = session.open(path).await?;
-        let reader = ReaderStream::new(remote_file);
+        let content_length = remote_file.metadata().await?.size;
+        let bytes_received = self.bytes_received.clone();
+        let on_progress = self.on_progress.clone();
+        let mut transferred = 0;
+        let reader = ReaderStream::with_capacity(remote_file, self.buffer_size).inspect(move |chunk| {
+            if let Result::Ok(chunk) = chunk {
+                bytes_received.fetch_add(chunk.len() as u64, Ordering::Relaxed);
+                transferred += chunk.len() as u64;
+                if let Some(on_progress) = &on_progress {
+                    on_progress(transferred, content_length);
+                }
+            }
+        });
 
-        Ok(ByteStream::new(reader))
+        let stream = ByteStream::new(reader);
+        Ok(match content_length {
+            Some(content_length) => stream.with_content_length(content_length),
+            None => stream,
+        })
+    }
+
+    /// Downloads the file to a local path and returns the number of bytes written.
+    pub async fn to_path(&mut self, local_path: impl AsRef<Path>) -> anyhow::Result<u64> {
+        let local_path = local_path.as_ref();
+        self.retry(async |client: &Self, session: Arc<SftpSession>| {
+            let path = client.path.as_ref().unwrap().to_string_lossy();
+            tracing::trace!("SFTP downloading {:?} to {:?}", path, local_path);
+
+            let mut remote_file = session.open(path).await?;
+            let total = remote_file.metadata().await?.size;
+            let mut local_file = tokio::fs::File::create(local_path).await?;
+            let bytes = client.copy(&mut remote_file, &mut local_file, total, &client.bytes_received).await?;
+            local_file.flush().await?;
+            remote_file.shutdown().await?;
+
+            Ok(bytes)
+        }).await
     }
 }
 
 impl SftpClient<PutFile> {
-    pub async fn from_bytes(&mut self, bytes: impl Into<Bytes>) -> anyhow::Result<()> {
-        let session = self.get_session().await?;
-        let path = self.path.as_ref().unwrap().to_string_lossy();
-        tracing::trace!("SFTP uploading bytes to {:?}", path);
+    /// Uploads to a hidden `.name.part` file next to the target and renames it to the target once complete, so that
+    /// watchers of the remote directory never see a partially uploaded file. The temporary file is removed if the upload fails.
+    /// 
+    /// An existing file is replaced in one step when the server offers `posix-rename@openssh.com`. Otherwise it is moved
+    /// aside to `.name.bak` first and restored if the rename fails, so the target is briefly missing. Disabled by default.
+    pub fn atomic(mut self, atomic: bool) -> Self {
+        self.atomic = atomic;
+        self
+    }
 
-        let mut remote_file = session.create(path).await?;
-        remote_file.write_all(&bytes.into()).await?;
-        remote_file.shutdown().await?;
+    /// Uploads a local file and returns the number of bytes written.
+    pub async fn from_path(&mut self, local_path: impl AsRef<Path>) -> anyhow::Result<u64> {
+        let local_path = local_path.as_ref();
+        self.retry(async |client: &Self, session: Arc<SftpSession>| {
+            let path = client.upload_path();
+            tracing::trace!("SFTP uploading {:?} to {:?}", local_path, path);
+
+            let result = async {
+                let mut local_file = tokio::fs::File::open(local_path).await?;
+                let total = local_file.metadata().await?.len();
+                let mut remote_file = session.create(path).await?;
+                let bytes = client.copy(&mut local_file, &mut remote_file, Some(total), &client.bytes_sent).await?;
+                remote_file.shutdown().await?;
+                Ok(bytes)
+            }.await;
+
+            client.complete_upload(&session, result).await
+        }).await
+    }
 
-        tracing::trace!("SFTP upload complete");
-        Ok(())
+    /// Uploads the bytes and returns the number of bytes written.
+    pub async fn from_bytes(&mut self, bytes: impl Into<Bytes>) -> anyhow::Result<u64> {
+        let bytes = bytes.into();
+        self.retry(async |client: &Self, session: Arc<SftpSession>| {
+            let path = client.upload_path();
+            tracing::trace!("SFTP uploading bytes to {:?}", path);
+
+            let result = async {
+                let mut remote_file = session.create(path).await?;
+                let written = client.copy(&mut bytes.as_ref(), &mut remote_file, Some(bytes.len() as u64), &client.bytes_sent).await?;
+                remote_file.shutdown().await?;
+                Ok(written)
+            }.await;
+
+            client.complete_upload(&session, result).await
+        }).await
     }
 
-    pub async fn from_stream(&mut self, mut stream: ByteStream) -> anyhow::Result<()> {
+    /// Uploads the stream and returns the number of bytes written.
+    pub async fn from_stream(&mut self, mut stream: ByteStream) -> anyhow::Result<u64> {
         let session = self.get_session().await?;
-        let path = self.path.as_ref().unwrap().to_string_lossy();
+        let path = self.upload_path();
         tracing::trace!("SFTP uploading bytes to {:?}", path);
 
-        let mut remote_file = session.create(path).await?;
-        
-        while let Some(chunk) = stream.next().await {
-            let chunk = chunk?; 
-            remote_file.write_all(&chunk).await?;
+        let result = async {
+            let mut remote_file = session.create(path).await?;
+            let total = stream.content_length();
+            let mut written: u64 = 0;
+
+            while let Some(chunk) = stream.next().await {
+                let chunk = chunk?; 
+                remote_file.write_all(&chunk).await?;
+                written += chunk.len() as u64;
+                self.bytes_sent.fetch_add(chunk.len() as u64, Ordering::Relaxed);
+                if let Some(on_progress) = &self.on_progress {
+                    on_progress(written, total);
+                }
+            }
+            remote_file.shutdown().await?;
+            Ok(written)
+        }.await;
+
+        self.complete_upload(&session, result).await
+    }
+
+    /// The remote path written to during the upload, the temporary `.name.part` path when atomic.
+    fn upload_path(&self) -> String {
+        match self.atomic {
+            true => self.hidden_path("part"),
+            false => None,
+        }.unwrap_or_else(|| self.path.as_ref().unwrap().to_string_lossy().to_string())
+    }
+
+    /// The hidden `.name.suffix` path next to the target.
+    fn hidden_path(&self, suffix: &str) -> Option<String> {
+        let path = self.path.as_ref().unwrap();
+        path.file_name().map(|name| path.with_file_name(format!(".{}.{}", name.to_string_lossy(), suffix)).to_string_lossy().to_string())
+    }
+
+    /// Renames a completed atomic upload to the target path, or removes the temporary file if the upload or rename failed.
+    async fn complete_upload(&self, session: &SftpSession, result: anyhow::Result<u64>) -> anyhow::Result<u64> {
+        let temp_path = self.upload_path();
+        let path = self.path.as_ref().unwrap().to_string_lossy().to_string();
+        if temp_path == path {
+            return result.inspect(|_| tracing::trace!("SFTP upload complete"));
+        }
+
+        let result = match result {
+            Result::Ok(written) => self.rename(session, &temp_path, &path).await.map(|_| written),
+            Err(err) => Err(err),
+        };
+        if result.is_err() && let Err(err) = session.remove_file(temp_path.as_str()).await {
+            tracing::warn!("SFTP failed to remove temporary file {:?}: {}", temp_path, err);
+        }
+
+        result.inspect(|_| tracing::trace!("SFTP upload complete"))
+    }
+
+    /// Renames the temporary file over the target, never leaving the target removed if the rename fails.
+    async fn rename(&self, session: &SftpSession, from: &str, to: &str) -> anyhow::Result<()> {
+        tracing::trace!("SFTP renaming {:?} to {:?}", from, to);
+        if let Some(raw) = self.posix_rename_session().await {
+            let mut data = Vec::new();
+            for path in [from, to] {
+                data.extend_from_slice(&(path.len() as u32).to_be_bytes());
+                data.extend_from_slice(path.as_bytes());
+            }
+
+            return match raw.extended(POSIX_RENAME, data).await? {
+                Packet::Status(status) if status.status_code == StatusCode::Ok => Ok(()),
+                Packet::Status(status) => Err(SftpError::Status(status).into()),
+                _ => Err(SftpError::UnexpectedPacket.into()),
+            };
+        }
+
+        let err = match session.rename(from, to).await {
+            Result::Ok(()) => return Ok(()),
+            Err(err) => err,
+        };
+
+        // Servers following SFTP version 3 refuse to rename over an existing file with a generic failure.
+        let target_exists = matches!(&err, SftpError::Status(status) if status.status_code == StatusCode::Failure)
+            && session.symlink_metadata(to).await.is_ok_and(|metadata| metadata.is_regular());
+        let Some(backup) = self.hidden_path("bak").filter(|_| target_exists) else {
+            return Err(err.into());
+        };
+
+        tracing::trace!("SFTP moving existing file {:?} aside to {:?}", to, backup);
+        let _ = session.remove_file(backup.as_str()).await;
+        session.rename(to, backup.as_str()).await?;
+
+        if let Err(err) = session.rename(from, to).await {
+            if let Err(restore_err) = session.rename(backup.as_str(), to).await {
+                tracing::error!("SFTP failed to restore {:?} from {:?}: {}", to, backup, restore_err);
+            }
+            return Err(err.into());
         }
-        remote_file.shutdown().await?;
 
-        tracing::trace!("SFTP upload complete");
+        if let Err(err) = session.remove_file(backup.as_str()).await {
+            tracing::warn!("SFTP failed to remove replaced file {:?}: {}", backup, err);
+        }
         Ok(())
     }
+
+    /// Returns the raw SFTP channel for [`POSIX_RENAME`] of the current connection, opening it on first use.
+    /// 
+    /// Sessions passed to [`from_session`](SftpClient::from_session) have no connection of their own and always return `None`.
+    async fn posix_rename_session(&self) -> Option<Arc<RawSftpSession>> {
+        let guard = self.session.lock().await;
+        let connection = guard.as_ref()?;
+        connection.posix_rename.get_or_init(async || {
+            match Self::open_posix_rename(&connection.ssh).await {
+                Result::Ok(raw) => raw.map(Arc::new),
+                Err(err) => {
+                    tracing::warn!("SFTP failed to check for {}: {}", POSIX_RENAME, err);
+                    None
+                },
+            }
+        }).await.clone()
+    }
+
+    async fn open_posix_rename(ssh: &Handle<SshClient>) -> anyhow::Result<Option<RawSftpSession>> {
+        let channel = ssh.channel_open_session().await?;
+        channel.request_subsystem(true, "sftp").await?;
+        let raw = RawSftpSession::new(channel.into_stream());
+        let version = raw.init().await?;
+        if version.extensions.get(POSIX_RENAME).is_some_and(|version| version == "1") {
+            return Ok(Some(raw));
+        }
+
+        raw.close_session()?;
+        Ok(None)
+    }
+}
+
+impl SftpClient<GetDir> {
+    /// Descends into subdirectories, recreating the directory structure under the local path. Disabled by default.
+    pub fn recursive(mut self, recursive: bool) -> Self {
+        self.recursive = recursive;
+        self
+    }
+
+    /// Only downloads files whose path relative to the remote directory matches the regex, e.g. `^reports/.*\.csv$`.
+    /// 
+    /// Fails if the regex is not valid.
+    pub fn regex(mut self, regex: impl AsRef<str>) -> anyhow::Result<Self> {
+        self.regex = Some(Regex::new(regex.as_ref())?);
+        Ok(self)
+    }
+
+    /// Deletes each remote file after it has been downloaded, directories are never deleted. Disabled by default.
+    pub fn delete_after_download(mut self, delete: bool) -> Self {
+        self.delete_after_download = delete;
+        self
+    }
+
+    /// Lists the files that [`to_path`](SftpClient::to_path) would download, with their sizes and modification times,
+    /// without downloading anything.
+    pub async fn list(&mut self) -> anyhow::Result<Vec<SftpFileEntry>> {
+        let files = self.retry(async |client: &Self, session: Arc<SftpSession>| client.remote_files(&session).await).await?;
+        Ok(files.into_iter().map(|(_, entry)| entry).collect())
+    }
+
+    /// Downloads the files to the local directory and returns their paths relative to the remote directory.
+    /// 
+    /// Symbolic links are followed, directories already visited are skipped to guard against cycles.
+    /// 
+    /// Each file is downloaded again on a new connection if the connection is lost, files already downloaded are kept.
+    pub async fn to_path(&mut self, local_path: impl AsRef<Path>) -> anyhow::Result<Vec<PathBuf>> {
+        let files = self.retry(async |client: &Self, session: Arc<SftpSession>| client.remote_files(&session).await).await?;
+        let mut downloaded = Vec::new();
+
+        for (remote_path, entry) in files {
+            let target = local_path.as_ref().join(&entry.path);
+            if let Some(parent) = target.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+
+            self.retry(async |client: &Self, session: Arc<SftpSession>| {
+                tracing::trace!("SFTP downloading {:?} to {:?}", remote_path, target);
+                let mut remote_file = session.open(remote_path.as_str()).await?;
+                let mut local_file = tokio::fs::File::create(&target).await?;
+                client.copy(&mut remote_file, &mut local_file, entry.size, &client.bytes_received).await?;
+                local_file.flush().await?;
+                remote_file.shutdown().await?;
+                Ok(())
+            }).await?;
+
+            if self.delete_after_download {
+                tracing::trace!("SFTP removing file {:?}", remote_path);
+                self.get_session().await?.remove_file(remote_path).await?;
+            }
+
+            downloaded.push(entry.path);
+        }
+
+        Ok(downloaded)
+    }
+
+    /// Walks the remote directory and returns the remote path and entry of every regular file matching the regex.
+    async fn remote_files(&self, session: &SftpSession) -> anyhow::Result<Vec<(String, SftpFileEntry)>> {
+        let root = self.path.as_ref().unwrap().to_string_lossy().to_string();
+        let mut visited = HashSet::new();
+        let mut pending = vec![(root, String::new())];
+        let mut files = Vec::new();
+
+        while let Some((remote_dir, relative_dir)) = pending.pop() {
+            if !visited.insert(session.canonicalize(remote_dir.as_str()).await?) {
+                tracing::trace!("SFTP skipping already visited directory {:?}", remote_dir);
+                continue;
+            }
+
+            for entry in session.read_dir(remote_dir.as_str()).await? {
+                let remote_path = format!("{}/{}", remote_dir.trim_end_matches('/'), entry.file_name());
+                let relative_path = match relative_dir.is_empty() {
+                    true => entry.file_name(),
+                    false => format!("{}/{}", relative_dir, entry.file_name()),
+                };
+
+                let metadata = match entry.file_type().is_symlink() {
+                    true => session.metadata(remote_path.as_str()).await?,
+                    false => entry.metadata(),
+                };
+
+                if metadata.is_dir() {
+                    if self.recursive {
+                        pending.push((remote_path, relative_path));
+                    }
+                    continue;
+                }
+
+                if !metadata.is_regular() || self.regex.as_ref().is_some_and(|regex| !regex.is_match(&relative_path)) {
+                    continue;
+                }
+
+                files.push((remote_path, SftpFileEntry {
+                    path: PathBuf::from(relative_path),
+                    size: metadata.size,
+                    modified: metadata.modified().ok(),
+                }));
+            }
+        }
+
+        Ok(files)
+    }
 }
 
 impl<State> SftpClient<State> {
-    async fn get_session(&mut self) -> anyhow::Result<SftpSession> {
+    /// Registers a callback invoked as file transfers progress, with the bytes transferred so far and the total bytes if known.
+    /// 
+    /// The callback is passed on to clients created from this client, e.g. with [`get_file`](SftpClient::get_file).
+    pub fn on_progress<T>(mut self, callback: T) -> Self
+    where
+        T: Fn(u64, Option<u64>) + Send + Sync + 'static,
+    {
+        self.on_progress = Some(Arc::new(callback));
+        self
+    }
+
+    /// Sets the size of the buffer used for each read and write during transfers, defaults to [`DEFAULT_BUFFER_SIZE`].
+    /// 
+    /// Larger buffers need fewer round trips to the server for big files.
+    pub fn buffer_size(mut self, buffer_size: usize) -> Self {
+        assert!(buffer_size > 0, "Buffer size must be greater than zero.");
+        self.buffer_size = buffer_size;
+        self
+    }
+
+    /// Copies the reader to the writer in chunks, adding to the byte counter and reporting progress after each chunk.
+    pub(crate) async fn copy<R, W>(&self, reader: &mut R, writer: &mut W, total: Option<u64>, counter: &AtomicU64) -> anyhow::Result<u64>
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let mut buffer = vec![0u8; self.buffer_size];
+        let mut transferred: u64 = 0;
+
+        loop {
+            let read = reader.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+
+            writer.write_all(&buffer[..read]).await?;
+            transferred += read as u64;
+            counter.fetch_add(read as u64, Ordering::Relaxed);
+            if let Some(on_progress) = &self.on_progress {
+                on_progress(transferred, total);
+            }
+        }
+
+        Ok(transferred)
+    }
+
+    /// Lists the names of the entries in a remote directory.
+    pub async fn list_dir(&mut self, path: impl AsRef<Path>) -> anyhow::Result<Vec<String>> {
+        let session = self.get_session().await?;
+        let path = path.as_ref().to_string_lossy();
+
+        Ok(session.read_dir(path).await?.map(|entry| entry.file_name()).collect())
+    }
+
+    /// Closes the SFTP subsystem and disconnects the SSH session shared by this client and all clients derived from it.
+    /// 
+    /// The connection is also closed when the last client is dropped, and reopened if the client is used again.
+    /// Sessions passed to [`from_session`](SftpClient::from_session) are owned by the caller and are not disconnected.
+    pub async fn close(&mut self) -> anyhow::Result<()> {
+        if let Some(sftp) = self.sftp.take() {
+            sftp.close().await?;
+            return Ok(());
+        }
+
+        if let Some(connection) = self.session.lock().await.take() {
+            tracing::trace!("SSH disconnecting");
+            connection.sftp.close().await?;
+            if let Some(Some(raw)) = connection.posix_rename.get() {
+                raw.close_session()?;
+            }
+            connection.ssh.disconnect(russh::Disconnect::ByApplication, "", "en").await?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the total number of bytes uploaded by this client and all clients derived from it.
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent.load(Ordering::Relaxed)
+    }
+
+    /// Returns the total number of bytes downloaded by this client and all clients derived from it.
+    pub fn bytes_received(&self) -> u64 {
+        self.bytes_received.load(Ordering::Relaxed)
+    }
+
+    /// Returns the shared session, connecting with retries as configured by
+    /// [`retry`](crate::sftp::sftp_client_config::SftpClientConfigBuilder::retry) when not connected.
+    pub(crate) async fn get_session(&self) -> anyhow::Result<Arc<SftpSession>> {
+        self.retry(async |_: &Self, session: Arc<SftpSession>| Ok(session)).await
+    }
+
+    /// Runs the operation on the shared session, reconnecting and running it again when the connection is lost as configured by
+    /// [`retry`](crate::sftp::sftp_client_config::SftpClientConfigBuilder::retry). Connecting and running the operation share the same attempts.
+    async fn retry<T>(&self, operation: impl AsyncFn(&Self, Arc<SftpSession>) -> anyhow::Result<T>) -> anyhow::Result<T> {
+        let mut attempt = 1;
+        loop {
+            let result = match self.connect().await {
+                Result::Ok(session) => operation(self, session).await,
+                Err(err) => Err(err),
+            };
+
+            match result {
+                Err(err) if attempt < self.max_attempts() && Self::is_transient(&err) => {
+                    *self.session.lock().await = None;
+                    self.retry_wait(attempt, &err).await;
+                    attempt += 1;
+                },
+                result => return result,
+            }
+        }
+    }
+
+    /// Returns the shared session, opening a new connection if there is none or it was closed.
+    async fn connect(&self) -> anyhow::Result<Arc<SftpSession>> {
+        if let Some(sftp) = &self.sftp {
+            return Ok(sftp.clone());
+        }
+
         let mut guard = self.session.lock().await;
 
-        let session = match guard.take() {
-            Some(session) if !session.is_closed() => {
-                tracing::trace!("SSH session reused");
-                session
-            },
-            _ => self.connect_session().await?
-        };
+        if let Some(connection) = guard.as_ref() && !connection.ssh.is_closed() {
+            tracing::trace!("SSH session reused");
+            return Ok(connection.sftp.clone());
+        }
 
-        let sftp = self.connect_sftp(&session).await?;
-        *guard = Some(session);
+        let ssh = self.connect_session().await?;
+        let sftp = Arc::new(self.connect_sftp(&ssh).await?);
+        *guard = Some(SftpConnection { ssh, sftp: sftp.clone(), posix_rename: OnceCell::new() });
         Ok(sftp)
     }
 
+    fn max_attempts(&self) -> u32 {
+        self.config.as_ref().map(|config| config.max_attempts).unwrap_or(1)
+    }
+
+    async fn retry_wait(&self, attempt: u32, err: &anyhow::Error) {
+        let backoff = self.config.as_ref().map(|config| config.retry_backoff).unwrap_or_default();
+        let delay = utils::retry_delay(backoff, attempt, MAX_RETRY_DELAY);
+        tracing::warn!("SFTP attempt {} failed with {}, retrying in {:?}", attempt, err, delay);
+        tokio::time::sleep(delay).await;
+    }
+
+    /// Whether the error is a lost or refused connection that may succeed on retry, unlike authentication failures.
+    fn is_transient(err: &anyhow::Error) -> bool {
+        let transient_io = |kind: ErrorKind| matches!(kind, ErrorKind::ConnectionRefused | ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted
+            | ErrorKind::NotConnected | ErrorKind::BrokenPipe | ErrorKind::TimedOut | ErrorKind::UnexpectedEof | ErrorKind::HostUnreachable | ErrorKind::NetworkUnreachable);
+
+        err.chain().any(|cause| {
+            if let Some(err) = cause.downcast_ref::<russh::Error>() {
+                return match err {
+                    russh::Error::IO(err) => transient_io(err.kind()),
+                    err => matches!(err, russh::Error::Disconnect | russh::Error::HUP | russh::Error::ConnectionTimeout | russh::Error::KeepaliveTimeout
+                        | russh::Error::InactivityTimeout | russh::Error::SendError | russh::Error::RecvError | russh::Error::Elapsed(_)),
+                };
+            }
+            if let Some(err) = cause.downcast_ref::<russh_sftp::client::error::Error>() {
+                return matches!(err, russh_sftp::client::error::Error::IO(_) | russh_sftp::client::error::Error::Timeout);
+            }
+            cause.downcast_ref::<std::io::Error>().is_some_and(|err| transient_io(err.kind()))
+        })
+    }
+
     async fn connect_session(&self) -> anyhow::Result<Handle<SshClient>> {
-        let config = self.config.clone();
+        let config = self.config.clone().ok_or_else(|| anyhow::anyhow!("No config to connect with"))?;
         tracing::trace!("SSH connecting to {}", config.endpoint);
-        let mut session = russh::client::connect(Arc::new(russh::client::Config::default()), &config.endpoint, SshClient {}).await?;
+        let ssh_config = russh::client::Config {
+            preferred: config.preferred.clone(),
+            keepalive_interval: config.keepalive_interval,
+            ..Default::default()
+        };
+        let (host, port) = utils::parse_host(&config.endpoint, 22)?;
+        let ssh_client = SshClient {
+            host: host.to_string(),
+            port,
+            known_hosts: config.known_hosts.clone(),
+            trust_on_first_use: config.trust_on_first_use,
+        };
+        let connect = russh::client::connect(Arc::new(ssh_config), &config.endpoint, ssh_client);
+        let mut session = match config.connect_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, connect).await.map_err(|_| {
+                std::io::Error::new(ErrorKind::TimedOut, format!("SSH connection to {} timed out after {:?}", config.endpoint, timeout))
+            })??,
+            None => connect.await?,
+        };
         
         let mut authenticated = false;
 
@@ -1,4 +1,6 @@
-use std::{marker::PhantomData, path::PathBuf};
+use std::{borrow::Cow, marker::PhantomData, path::PathBuf, time::Duration};
+
+use russh::{Preferred, cipher, kex, keys::Algorithm, mac};
 
 use crate::sftp::{sftp_auth_basic::SftpAuthBasic, sftp_auth_private_key::SftpAuthPrivateKey};
 
@@ -6,6 +8,13 @@ pub struct SftpClientConfig {
     pub endpoint: String,
     pub auth_basic: Option<SftpAuthBasic>,
     pub auth_private_key: Option<SftpAuthPrivateKey>,
+    pub preferred: Preferred,
+    pub known_hosts: Option<PathBuf>,
+    pub trust_on_first_use: bool,
+    pub max_attempts: u32,
+    pub retry_backoff: Duration,
+    pub connect_timeout: Option<Duration>,
+    pub keepalive_interval: Option<Duration>,
 }
 
 impl SftpClientConfig {
@@ -14,6 +23,16 @@ impl SftpClientConfig {
             endpoint: None,
             auth_basic: None,
             auth_private_key: None,
+            kex_algorithms: None,
+            cipher_algorithms: None,
+            mac_algorithms: None,
+            host_key_algorithms: None,
+            known_hosts: None,
+            trust_on_first_use: false,
+            max_attempts: 1,
+            retry_backoff: Duration::from_millis(500),
+            connect_timeout: None,
+            keepalive_interval: None,
             _state: PhantomData
         }
     }
@@ -26,6 +45,16 @@ pub struct SftpClientConfigBuilder<State> {
     pub endpoint: Option<String>,
     pub auth_basic: Option<SftpAuthBasic>,
     pub auth_private_key: Option<SftpAuthPrivateKey>,
+    pub kex_algorithms: Option<Vec<String>>,
+    pub cipher_algorithms: Option<Vec<String>>,
+    pub mac_algorithms: Option<Vec<String>>,
+    pub host_key_algorithms: Option<Vec<String>>,
+    pub known_hosts: Option<PathBuf>,
+    pub trust_on_first_use: bool,
+    pub max_attempts: u32,
+    pub retry_backoff: Duration,
+    pub connect_timeout: Option<Duration>,
+    pub keepalive_interval: Option<Duration>,
     _state: PhantomData<State>,
 }
 
@@ -35,6 +64,16 @@ impl SftpClientConfigBuilder<SetEndpoint> {
             endpoint: Some(endpoint.into()),
             auth_basic: self.auth_basic,
             auth_private_key: self.auth_private_key,
+            kex_algorithms: self.kex_algorithms,
+            cipher_algorithms: self.cipher_algorithms,
+            mac_algorithms: self.mac_algorithms,
+            host_key_algorithms: self.host_key_algorithms,
+            known_hosts: self.known_hosts,
+            trust_on_first_use: self.trust_on_first_use,
+            max_attempts: self.max_attempts,
+            retry_backoff: self.retry_backoff,
+            connect_timeout: self.connect_timeout,
+            keepalive_interval: self.keepalive_interval,
             _state: PhantomData
         }
     }
@@ -68,11 +107,113 @@ impl SftpClientConfigBuilder<Optional> {
         self
     }
 
+    /// Sets the preferred key exchange algorithms in order of preference, e.g. `diffie-hellman-group14-sha1`.
+    /// 
+    /// Defaults to the `russh` algorithm set.
+    pub fn kex_algorithms(mut self, algorithms: &[&str]) -> Self {
+        self.kex_algorithms = Some(algorithms.iter().map(|a| a.to_string()).collect());
+        self
+    }
+
+    /// Sets the preferred cipher algorithms in order of preference, e.g. `aes256-ctr`.
+    /// 
+    /// Defaults to the `russh` algorithm set.
+    pub fn cipher_algorithms(mut self, algorithms: &[&str]) -> Self {
+        self.cipher_algorithms = Some(algorithms.iter().map(|a| a.to_string()).collect());
+        self
+    }
+
+    /// Sets the preferred MAC algorithms in order of preference, e.g. `hmac-sha2-256`.
+    /// 
+    /// Defaults to the `russh` algorithm set.
+    pub fn mac_algorithms(mut self, algorithms: &[&str]) -> Self {
+        self.mac_algorithms = Some(algorithms.iter().map(|a| a.to_string()).collect());
+        self
+    }
+
+    /// Sets the preferred host key algorithms in order of preference, e.g. `ssh-ed25519`.
+    /// 
+    /// Defaults to the `russh` algorithm set.
+    pub fn host_key_algorithms(mut self, algorithms: &[&str]) -> Self {
+        self.host_key_algorithms = Some(algorithms.iter().map(|a| a.to_string()).collect());
+        self
+    }
+
+    /// Verifies the server host key against an OpenSSH `known_hosts` file, rejecting unknown and changed keys.
+    /// 
+    /// **By default no verification is done and any server host key is accepted.**
+    pub fn known_hosts(mut self, path: impl Into<PathBuf>) -> Self {
+        self.known_hosts = Some(path.into());
+        self
+    }
+
+    /// Accepts unknown host keys and appends them to the [`known_hosts`](Self::known_hosts) file, pinning them for later connections.
+    /// 
+    /// Changed host keys are still rejected. Disabled by default.
+    pub fn trust_on_first_use(mut self, enabled: bool) -> Self {
+        self.trust_on_first_use = enabled;
+        self
+    }
+
+    /// Sets the max time to open the TCP connection and complete the SSH handshake before failing with a timeout error.
+    /// 
+    /// Timeouts are retried like other connection failures when [`retry`](Self::retry) is configured. No timeout by default.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Sends SSH keepalive messages at the interval, disconnecting when the server stops answering them.
+    /// 
+    /// Detects dead connections during long transfers and between operations. Disabled by default.
+    pub fn keepalive_interval(mut self, interval: Duration) -> Self {
+        self.keepalive_interval = Some(interval);
+        self
+    }
+
+    /// Retries transient connection failures until the operation has been attempted `max_attempts` times.
+    /// 
+    /// Connecting and authenticating is retried for every operation. Uploads from a path or bytes, downloads to bytes or
+    /// a path and listings are restarted on a new connection if it is lost during the transfer, with connecting and
+    /// transferring sharing the same attempts. Authentication failures and rejected host keys are never retried.
+    /// The delay starts at `backoff`, doubles for every retry and has random jitter added. Disabled by default.
+    pub fn retry(mut self, max_attempts: u32, backoff: Duration) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self.retry_backoff = backoff;
+        self
+    }
+
     pub fn build(self) -> anyhow::Result<SftpClientConfig> {
+        let mut preferred = Preferred::default();
+
+        if let Some(algorithms) = self.kex_algorithms {
+            let names = algorithms.iter().map(|a| kex::Name::try_from(a.as_str()).map_err(|_| anyhow::anyhow!("Unsupported kex algorithm: {}", a))).collect::<anyhow::Result<Vec<_>>>()?;
+            preferred.kex = Cow::Owned(names);
+        }
+        if let Some(algorithms) = self.cipher_algorithms {
+            let names = algorithms.iter().map(|a| cipher::Name::try_from(a.as_str()).map_err(|_| anyhow::anyhow!("Unsupported cipher algorithm: {}", a))).collect::<anyhow::Result<Vec<_>>>()?;
+            preferred.cipher = Cow::Owned(names);
+        }
+        if let Some(algorithms) = self.mac_algorithms {
+            let names = algorithms.iter().map(|a| mac::Name::try_from(a.as_str()).map_err(|_| anyhow::anyhow!("Unsupported mac algorithm: {}", a))).collect::<anyhow::Result<Vec<_>>>()?;
+            preferred.mac = Cow::Owned(names);
+        }
+        if let Some(algorithms) = self.host_key_algorithms {
+            let names = algorithms.iter().map(|a| Algorithm::new(a).map_err(|_| anyhow::anyhow!("Unsupported host key algorithm: {}", a))).collect::<anyhow::Result<Vec<_>>>()?;
+            preferred.key = Cow::Owned(names);
+        }
+
         Ok(SftpClientConfig {
             endpoint: self.endpoint.ok_or_else(|| anyhow::anyhow!("Endpoint not found"))?,
             auth_basic: self.auth_basic,
-            auth_private_key: self.auth_private_key
+            auth_private_key: self.auth_private_key,
+            preferred,
+            known_hosts: self.known_hosts,
+            trust_on_first_use: self.trust_on_first_use,
+            max_attempts: self.max_attempts,
+            retry_backoff: self.retry_backoff,
+            connect_timeout: self.connect_timeout,
+            keepalive_interval: self.keepalive_interval,
         })
     }
 }
\ No newline at end of file
@@ -0,0 +1,12 @@
+use std::{path::PathBuf, time::SystemTime};
+
+/// A remote file found by [`list`](crate::sftp::sftp_client::SftpClient::list).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SftpFileEntry {
+    /// The path relative to the listed remote directory.
+    pub path: PathBuf,
+    /// The size in bytes, if reported by the server.
+    pub size: Option<u64>,
+    /// The last modification time, if reported by the server.
+    pub modified: Option<SystemTime>,
+}
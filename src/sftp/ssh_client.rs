@@ -1,9 +1,33 @@
-pub struct SshClient;
+use std::path::PathBuf;
+
+use russh::keys::known_hosts::{check_known_hosts_path, learn_known_hosts_path};
+
+pub struct SshClient {
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    pub(crate) known_hosts: Option<PathBuf>,
+    pub(crate) trust_on_first_use: bool,
+}
 
 impl russh::client::Handler for SshClient {
     type Error = anyhow::Error;
 
-    async fn check_server_key(&mut self, _server_public_key: &russh::keys::PublicKey) -> Result<bool, anyhow::Error> {
-        Ok(true)
+    async fn check_server_key(&mut self, server_public_key: &russh::keys::PublicKey) -> Result<bool, anyhow::Error> {
+        let path = match &self.known_hosts {
+            Some(path) => path,
+            None => return Ok(true),
+        };
+
+        match check_known_hosts_path(&self.host, self.port, server_public_key, path) {
+            Ok(true) => Ok(true),
+            Ok(false) if self.trust_on_first_use => {
+                tracing::trace!("SSH pinning unknown host key for {}:{}", self.host, self.port);
+                learn_known_hosts_path(&self.host, self.port, server_public_key, path)?;
+                Ok(true)
+            },
+            Ok(false) => Err(anyhow::anyhow!("Host key for {}:{} not found in {:?}", self.host, self.port, path)),
+            Err(russh::keys::Error::KeyChanged { line }) => Err(anyhow::anyhow!("Host key for {}:{} does not match line {} in {:?}", self.host, self.port, line, path)),
+            Err(err) => Err(err.into()),
+        }
     }
-}
\ No newline at end of file
+}
@@ -7,7 +7,7 @@ async fn client_test() {
     let mut client = SftpClient::new(config);
 
     let result = client.put_file("upload/file_bytes.txt").from_bytes("hello world").await;
-    assert!(result.is_ok());
+    assert_eq!(result.unwrap(), 11);
 
     let result = client.get_file("upload/file_bytes.txt").as_bytes().await;
     assert!(result.is_ok());
@@ -21,8 +21,342 @@ async fn client_test() {
 
     let result = client.get_file("upload/file_stream.txt").as_stream().await;
     assert!(result.is_ok());
-    tracing::info!("{:?}", result.unwrap().as_bytes().await);
+    tracing::info!("{:?}", result.unwrap().to_bytes().await);
 
     let result = client.delete_file("upload/file_stream.txt").await;
     assert!(result.is_ok());
-}
\ No newline at end of file
+
+    assert_eq!(client.bytes_sent(), 22);
+    assert_eq!(client.bytes_received(), 22);
+}
+
+#[test]
+fn config_algorithms_test() {
+    let config = SftpClientConfig::builder().endpoint("127.0.0.1:2222").kex_algorithms(&["diffie-hellman-group14-sha1"]).cipher_algorithms(&["aes256-ctr"]).mac_algorithms(&["hmac-sha2-256"]).host_key_algorithms(&["ssh-ed25519"]).build();
+    assert!(config.is_ok());
+    assert_eq!(config.unwrap().preferred.kex[0].as_ref(), "diffie-hellman-group14-sha1");
+
+    let config = SftpClientConfig::builder().endpoint("127.0.0.1:2222").cipher_algorithms(&["not-a-cipher"]).build();
+    assert!(config.is_err());
+}
+
+#[tokio::test]
+async fn client_dir_test() {
+    let config = SftpClientConfig::builder().endpoint("127.0.0.1:2222").auth_basic("user", "password").build().unwrap();
+    let client = SftpClient::new(config);
+
+    let result = client.put_file("upload/dir_test.csv").from_bytes("a,b").await;
+    assert!(result.is_ok());
+
+    let _ = tokio::fs::remove_dir_all("/tmp/sftp_dir_test").await;
+    let result = client.get_dir("upload").recursive(true).regex(r"dir_test\.csv$").unwrap().delete_after_download(true).to_path("/tmp/sftp_dir_test").await;
+    assert!(result.unwrap().iter().any(|path| path.ends_with("dir_test.csv")));
+    assert_eq!(tokio::fs::read("/tmp/sftp_dir_test/dir_test.csv").await.unwrap(), b"a,b");
+
+    let result = client.get_dir("upload").regex(r"dir_test\.csv$").unwrap().to_path("/tmp/sftp_dir_test").await;
+    assert!(result.unwrap().is_empty());
+
+    assert!(client.get_dir("upload").regex("(unclosed").is_err());
+}
+
+#[tokio::test]
+async fn client_dir_nested_test() {
+    let config = SftpClientConfig::builder().endpoint("127.0.0.1:2222").auth_basic("user", "password").build().unwrap();
+    let client = SftpClient::new(config);
+    let session = client.get_session().await.unwrap();
+
+    for dir in ["upload/nested_test", "upload/nested_test/a", "upload/nested_test/a/b"] {
+        let _ = session.create_dir(dir).await;
+    }
+    assert!(client.put_file("upload/nested_test/top.csv").from_bytes("top").await.is_ok());
+    assert!(client.put_file("upload/nested_test/a/b/x.csv").from_bytes("x").await.is_ok());
+    assert!(client.put_file("upload/nested_test/a/b/skip.txt").from_bytes("skip").await.is_ok());
+    // Points back at the root, the walk must skip it rather than descend forever.
+    let _ = session.symlink("upload/nested_test/a/b/loop", "../..").await;
+
+    let _ = tokio::fs::remove_dir_all("/tmp/sftp_nested_test").await;
+    let result = client.get_dir("upload/nested_test").recursive(true).regex(r"\.csv$").unwrap().delete_after_download(true).to_path("/tmp/sftp_nested_test").await;
+    let mut downloaded = result.unwrap();
+    downloaded.sort();
+    assert_eq!(downloaded, vec![std::path::PathBuf::from("a/b/x.csv"), std::path::PathBuf::from("top.csv")]);
+    assert_eq!(tokio::fs::read("/tmp/sftp_nested_test/a/b/x.csv").await.unwrap(), b"x");
+    assert_eq!(tokio::fs::read("/tmp/sftp_nested_test/top.csv").await.unwrap(), b"top");
+    assert!(tokio::fs::metadata("/tmp/sftp_nested_test/a/b/skip.txt").await.is_err());
+
+    assert!(session.metadata("upload/nested_test/a/b").await.unwrap().is_dir());
+    assert!(session.try_exists("upload/nested_test/a/b/skip.txt").await.unwrap());
+    assert!(!session.try_exists("upload/nested_test/a/b/x.csv").await.unwrap());
+
+    let _ = session.remove_file("upload/nested_test/a/b/loop").await;
+    assert!(session.remove_file("upload/nested_test/a/b/skip.txt").await.is_ok());
+    for dir in ["upload/nested_test/a/b", "upload/nested_test/a", "upload/nested_test"] {
+        assert!(session.remove_dir(dir).await.is_ok());
+    }
+}
+
+#[tokio::test]
+async fn client_connection_test() {
+    let config = SftpClientConfig::builder().endpoint("127.0.0.1:2222").auth_basic("user", "password").build().unwrap();
+    let mut client = SftpClient::new(config);
+
+    tokio::fs::write("/tmp/sftp_connection_test.txt", "hello").await.unwrap();
+    let result = client.put_file("upload/connection_test.txt").from_path("/tmp/sftp_connection_test.txt").await;
+    assert_eq!(result.unwrap(), 5);
+
+    let result = client.list_dir("upload").await;
+    assert!(result.unwrap().contains(&String::from("connection_test.txt")));
+
+    let result = client.get_file("upload/connection_test.txt").to_path("/tmp/sftp_connection_test_download.txt").await;
+    assert_eq!(result.unwrap(), 5);
+
+    assert!(client.close().await.is_ok());
+
+    let result = client.delete_file("upload/connection_test.txt").await;
+    assert!(result.is_ok());
+    assert!(client.close().await.is_ok());
+}
+
+#[tokio::test]
+async fn known_hosts_test() {
+    use russh::client::Handler;
+    use crate::sftp::ssh_client::SshClient;
+
+    let path = std::path::PathBuf::from("/tmp/sftp_known_hosts_test");
+    let _ = tokio::fs::remove_file(&path).await;
+    let key = russh::keys::parse_public_key_base64("AAAAC3NzaC1lZDI1NTE5AAAAIMjuoddb2zQOZhq911mvRNEyCPpWOvrhFCVnJYFtWtWi").unwrap();
+    let changed_key = russh::keys::parse_public_key_base64("AAAAC3NzaC1lZDI1NTE5AAAAIFkc+ISt6ttbBgtMCgmJrmJwRAlmAyAjoQqkLIBJRUmD").unwrap();
+
+    let mut client = SshClient { host: String::from("127.0.0.1"), port: 2222, known_hosts: None, trust_on_first_use: false };
+    assert!(client.check_server_key(&key).await.unwrap());
+
+    client.known_hosts = Some(path.clone());
+    assert!(client.check_server_key(&key).await.is_err());
+
+    client.trust_on_first_use = true;
+    assert!(client.check_server_key(&key).await.unwrap());
+    assert!(client.check_server_key(&changed_key).await.is_err());
+
+    client.trust_on_first_use = false;
+    assert!(client.check_server_key(&key).await.unwrap());
+    assert!(client.check_server_key(&changed_key).await.is_err());
+}
+
+#[tokio::test]
+async fn progress_test() {
+    use std::sync::{Arc, Mutex, atomic::AtomicU64};
+
+    let config = SftpClientConfig::builder().endpoint("127.0.0.1:2222").auth_basic("user", "password").build().unwrap();
+    let progress = Arc::new(Mutex::new(Vec::new()));
+    let progress_clone = progress.clone();
+    let client = SftpClient::new(config).on_progress(move |transferred, total| progress_clone.lock().unwrap().push((transferred, total)));
+
+    let data = vec![7u8; 100_000];
+    let mut output = Vec::new();
+    let result = client.copy(&mut data.as_slice(), &mut output, Some(data.len() as u64), &AtomicU64::new(0)).await;
+    assert_eq!(result.unwrap(), 100_000);
+    assert_eq!(output, data);
+
+    let progress = progress.lock().unwrap();
+    assert!(progress.len() > 1);
+    assert!(progress.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    assert_eq!(progress.last().unwrap(), &(100_000, Some(100_000)));
+}
+
+#[tokio::test]
+async fn buffer_size_test() {
+    use std::sync::{Arc, atomic::{AtomicU64, Ordering}};
+
+    let config = SftpClientConfig::builder().endpoint("127.0.0.1:2222").auth_basic("user", "password").build().unwrap();
+    let operations = Arc::new(AtomicU64::new(0));
+    let operations_clone = operations.clone();
+    let client = SftpClient::new(config).buffer_size(256 * 1024).on_progress(move |_, _| { operations_clone.fetch_add(1, Ordering::Relaxed); });
+
+    let data = vec![7u8; 4 * 1024 * 1024];
+    let mut output = Vec::new();
+    let counter = AtomicU64::new(0);
+    let result = client.copy(&mut data.as_slice(), &mut output, Some(data.len() as u64), &counter).await;
+    assert_eq!(result.unwrap(), 4 * 1024 * 1024);
+    assert_eq!(counter.load(Ordering::Relaxed), 4 * 1024 * 1024);
+    assert_eq!(operations.load(Ordering::Relaxed), 16);
+}
+
+#[tokio::test]
+async fn retry_attempts_test() {
+    use std::{sync::{Arc, atomic::{AtomicU32, Ordering}}, time::Duration};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = listener.local_addr().unwrap().to_string();
+    let connections = Arc::new(AtomicU32::new(0));
+    let accepted = connections.clone();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            accepted.fetch_add(1, Ordering::Relaxed);
+            drop(stream);
+        }
+    });
+
+    let config = SftpClientConfig::builder().endpoint(&endpoint).auth_basic("user", "password").build().unwrap();
+    let result = SftpClient::new(config).put_file("upload/retry.txt").from_bytes("retry").await;
+    assert!(result.is_err());
+    assert_eq!(connections.swap(0, Ordering::Relaxed), 1);
+
+    let config = SftpClientConfig::builder().endpoint(&endpoint).auth_basic("user", "password").retry(3, Duration::from_millis(10)).build().unwrap();
+    let result = SftpClient::new(config).put_file("upload/retry.txt").from_bytes("retry").await;
+    assert!(result.is_err());
+    assert_eq!(connections.swap(0, Ordering::Relaxed), 3);
+
+    let config = SftpClientConfig::builder().endpoint(&endpoint).auth_basic("user", "password").retry(3, Duration::from_millis(10)).build().unwrap();
+    let client = SftpClient::new(config);
+    assert!(client.get_file("upload/retry.txt").as_bytes().await.is_err());
+    assert_eq!(connections.swap(0, Ordering::Relaxed), 3);
+
+    assert!(client.get_file("upload/retry.txt").to_path("/tmp/sftp_retry_test.txt").await.is_err());
+    assert_eq!(connections.swap(0, Ordering::Relaxed), 3);
+
+    assert!(client.get_dir("upload").to_path("/tmp/sftp_retry_test").await.is_err());
+    assert_eq!(connections.load(Ordering::Relaxed), 3);
+}
+
+#[tokio::test]
+async fn retry_test() {
+    use std::{sync::{Arc, atomic::{AtomicU32, Ordering}}, time::Duration};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = listener.local_addr().unwrap().to_string();
+    let connections = Arc::new(AtomicU32::new(0));
+    let accepted = connections.clone();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            if accepted.fetch_add(1, Ordering::Relaxed) == 0 {
+                continue;
+            }
+            tokio::spawn(async move {
+                let mut server = tokio::net::TcpStream::connect("127.0.0.1:2222").await.unwrap();
+                let _ = tokio::io::copy_bidirectional(&mut stream, &mut server).await;
+            });
+        }
+    });
+
+    let config = SftpClientConfig::builder().endpoint(&endpoint).auth_basic("user", "password").retry(3, Duration::from_millis(10)).build().unwrap();
+    let mut client = SftpClient::new(config);
+    let result = client.put_file("upload/retry_test.txt").from_bytes("retry").await;
+    assert_eq!(result.unwrap(), 5);
+    assert_eq!(connections.load(Ordering::Relaxed), 2);
+
+    let config = SftpClientConfig::builder().endpoint(&endpoint).auth_basic("user", "password").retry(3, Duration::from_millis(10)).build().unwrap();
+    connections.store(0, Ordering::Relaxed);
+    let result = SftpClient::new(config).get_file("upload/retry_test.txt").as_bytes().await;
+    assert_eq!(result.unwrap(), "retry");
+    assert_eq!(connections.load(Ordering::Relaxed), 2);
+    assert!(client.delete_file("upload/retry_test.txt").await.is_ok());
+
+    let config = SftpClientConfig::builder().endpoint(&endpoint).auth_basic("user", "wrong").retry(3, Duration::from_millis(10)).build().unwrap();
+    connections.store(1, Ordering::Relaxed);
+    let result = SftpClient::new(config).put_file("upload/retry_test.txt").from_bytes("retry").await;
+    assert!(result.is_err());
+    assert_eq!(connections.load(Ordering::Relaxed), 2);
+}
+
+#[tokio::test]
+async fn atomic_upload_test() {
+    let config = SftpClientConfig::builder().endpoint("127.0.0.1:2222").auth_basic("user", "password").build().unwrap();
+    let mut client = SftpClient::new(config);
+    let _ = client.delete_file("upload/atomic_test.bin").await;
+
+    let config = SftpClientConfig::builder().endpoint("127.0.0.1:2222").auth_basic("user", "password").build().unwrap();
+    let mut watcher_client = SftpClient::new(config);
+    let watcher = tokio::spawn(async move {
+        let mut observed = Vec::new();
+        for _ in 0..200 {
+            let names = watcher_client.list_dir("upload").await.unwrap();
+            observed.push((names.contains(&String::from(".atomic_test.bin.part")), names.contains(&String::from("atomic_test.bin"))));
+        }
+        observed
+    });
+
+    let result = client.put_file("upload/atomic_test.bin").atomic(true).from_bytes(vec![7u8; 8 * 1024 * 1024]).await;
+    assert_eq!(result.unwrap(), 8 * 1024 * 1024);
+
+    let observed = watcher.await.unwrap();
+    assert!(observed.iter().all(|(part, done)| !(*part && *done)));
+    let names = client.list_dir("upload").await.unwrap();
+    assert!(names.contains(&String::from("atomic_test.bin")));
+    assert!(!names.contains(&String::from(".atomic_test.bin.part")));
+
+    let result = client.put_file("upload/atomic_test.bin").atomic(true).from_bytes("replaced").await;
+    assert_eq!(result.unwrap(), 8);
+    assert_eq!(client.get_file("upload/atomic_test.bin").as_bytes().await.unwrap(), "replaced");
+
+    let chunks = vec![Ok(bytes::Bytes::from("partial")), Err(std::io::Error::other("failed"))];
+    let result = client.put_file("upload/atomic_failed.bin").atomic(true).from_stream(ByteStream::new(futures::stream::iter(chunks))).await;
+    assert!(result.is_err());
+    let names = client.list_dir("upload").await.unwrap();
+    assert!(!names.iter().any(|name| name.contains("atomic_failed")));
+
+    assert!(client.delete_file("upload/atomic_test.bin").await.is_ok());
+}
+
+#[tokio::test]
+async fn atomic_rename_failure_test() {
+    let config = SftpClientConfig::builder().endpoint("127.0.0.1:2222").auth_basic("user", "password").build().unwrap();
+    let mut client = SftpClient::new(config);
+    let session = client.get_session().await.unwrap();
+    let _ = session.create_dir("upload/atomic_rename_test").await;
+    assert!(client.put_file("upload/atomic_rename_test/keep.txt").from_bytes("keep").await.is_ok());
+
+    let result = client.put_file("upload/atomic_rename_test").atomic(true).from_bytes("replaced").await;
+    assert!(result.is_err());
+    assert_eq!(client.get_file("upload/atomic_rename_test/keep.txt").as_bytes().await.unwrap(), "keep");
+    let names = client.list_dir("upload").await.unwrap();
+    assert!(!names.iter().any(|name| name.starts_with(".atomic_rename_test")));
+
+    assert!(client.delete_file("upload/atomic_rename_test/keep.txt").await.is_ok());
+    assert!(session.remove_dir("upload/atomic_rename_test").await.is_ok());
+}
+
+#[tokio::test]
+async fn list_test() {
+    let config = SftpClientConfig::builder().endpoint("127.0.0.1:2222").auth_basic("user", "password").build().unwrap();
+    let mut client = SftpClient::new(config);
+
+    assert!(client.put_file("upload/list_test_a.csv").from_bytes("a,b").await.is_ok());
+    assert!(client.put_file("upload/list_test_b.txt").from_bytes("text").await.is_ok());
+
+    let result = client.get_dir("upload").regex(r"^list_test_.*\.csv$").unwrap().list().await.unwrap();
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].path, std::path::PathBuf::from("list_test_a.csv"));
+    assert_eq!(result[0].size, Some(3));
+    assert!(result[0].modified.is_some());
+
+    let result = client.get_dir("upload").regex(r"^list_test_").unwrap().list().await.unwrap();
+    assert_eq!(result.len(), 2);
+
+    let result = client.list_dir("upload").await.unwrap();
+    assert!(result.contains(&String::from("list_test_a.csv")) && result.contains(&String::from("list_test_b.txt")));
+
+    assert!(client.delete_file("upload/list_test_a.csv").await.is_ok());
+    assert!(client.delete_file("upload/list_test_b.txt").await.is_ok());
+}
+
+#[tokio::test]
+async fn connect_timeout_test() {
+    use std::time::{Duration, Instant};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = listener.local_addr().unwrap().to_string();
+    tokio::spawn(async move {
+        let mut streams = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            streams.push(stream);
+        }
+    });
+
+    let config = SftpClientConfig::builder().endpoint(&endpoint).auth_basic("user", "password").connect_timeout(Duration::from_millis(200)).keepalive_interval(Duration::from_secs(10)).build().unwrap();
+    let mut client = SftpClient::new(config);
+    let start = Instant::now();
+    let result = client.list_dir("upload").await;
+    assert!(start.elapsed() < Duration::from_secs(2));
+    let err = result.unwrap_err();
+    assert!(err.to_string().contains("timed out"));
+    assert_eq!(err.downcast_ref::<std::io::Error>().unwrap().kind(), std::io::ErrorKind::TimedOut);
+}
@@ -7,4 +7,8 @@ pub mod smtp_mode;
 #[cfg(feature = "smtp")]
 pub mod smtp_message;
 #[cfg(feature = "smtp")]
-pub mod smtp_sender;
\ No newline at end of file
+pub mod smtp_sender;
+
+#[cfg(feature = "smtp")]
+#[cfg(test)]
+mod test;
\ No newline at end of file
@@ -4,6 +4,15 @@ pub struct SmtpMessage {
     pub subject: String,
     pub body: String,
     pub content_type: SmtpContentType,
+    pub plain_alternative: Option<String>,
+    pub attachments: Vec<SmtpAttachment>,
+}
+
+/// A file attached to a message.
+pub struct SmtpAttachment {
+    pub file_name: String,
+    pub content_type: String,
+    pub body: Vec<u8>,
 }
 
 impl SmtpMessage {
@@ -12,6 +21,8 @@ impl SmtpMessage {
             subject: String::new(),
             body: String::new(),
             content_type: SmtpContentType::TextPlain,
+            plain_alternative: None,
+            attachments: Vec::new(),
         }
     }
 
@@ -29,4 +40,22 @@ impl SmtpMessage {
         self.content_type = content_type;
         self
     }
+
+    /// Sets both a plain text and an HTML body, sent as `multipart/alternative` so text-only clients still get content.
+    pub fn with_body_alternative<T: AsRef<str>>(mut self, plain: T, html: T) -> Self {
+        self.plain_alternative = Some(plain.as_ref().to_string());
+        self.body = html.as_ref().to_string();
+        self.content_type = SmtpContentType::TextHtml;
+        self
+    }
+
+    /// Attaches a file with the given content-type, e.g. `application/pdf`, sending the message as `multipart/mixed`.
+    pub fn with_attachment<T: AsRef<str>>(mut self, file_name: T, content_type: T, body: impl Into<Vec<u8>>) -> Self {
+        self.attachments.push(SmtpAttachment {
+            file_name: file_name.as_ref().to_string(),
+            content_type: content_type.as_ref().to_string(),
+            body: body.into(),
+        });
+        self
+    }
 }
\ No newline at end of file
@@ -1,14 +1,23 @@
-use lettre::{message::Mailbox, transport::smtp::authentication::Credentials, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::time::Duration;
+
+use lettre::{message::{header::ContentType, Attachment, Mailbox, MultiPart, SinglePart}, transport::smtp::authentication::Credentials, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
 
 use crate::{common::utils, smtp::{smtp_content_type::SmtpContentType, smtp_credentials::SmtpCredentials, smtp_message::SmtpMessage, smtp_mode::SmtpMode}};
 
+/// Upper bound for the delay between retries.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
 pub struct SmtpSender {
     host: String,
-    from: Vec<String>,
-    to: Vec<String>,
-    cc: Vec<String>,
+    from: Vec<(Option<String>, String)>,
+    to: Vec<(Option<String>, String)>,
+    cc: Vec<(Option<String>, String)>,
+    bcc: Vec<String>,
+    reply_to: Vec<String>,
     credentials: Option<SmtpCredentials>,
     mode: SmtpMode,
+    max_retries: u32,
+    retry_backoff: Duration,
 }
 
 impl SmtpSender {
@@ -18,8 +27,12 @@ impl SmtpSender {
             from: Vec::new(),
             to: Vec::new(),
             cc: Vec::new(),
+            bcc: Vec::new(),
+            reply_to: Vec::new(),
             credentials: None,
             mode: SmtpMode::RelayEsmtp,
+            max_retries: 0,
+            retry_backoff: Duration::from_secs(1),
         }
     }
 
@@ -29,17 +42,47 @@ impl SmtpSender {
     }
 
     pub fn from<T: AsRef<str>>(mut self, email_address: T) -> Self {
-        self.from.push(email_address.as_ref().to_string());
+        self.from.push((None, email_address.as_ref().to_string()));
+        self
+    }
+
+    /// Same as [`from`](Self::from) with a display name, e.g. `Acme Billing <billing@acme.com>`.
+    pub fn from_named<T: AsRef<str>>(mut self, name: T, email_address: T) -> Self {
+        self.from.push((Some(name.as_ref().to_string()), email_address.as_ref().to_string()));
         self
     }
 
     pub fn to<T: AsRef<str>>(mut self, email_address: T) -> Self {
-        self.to.push(email_address.as_ref().to_string());
+        self.to.push((None, email_address.as_ref().to_string()));
+        self
+    }
+
+    /// Same as [`to`](Self::to) with a display name, e.g. `Acme Billing <billing@acme.com>`.
+    pub fn to_named<T: AsRef<str>>(mut self, name: T, email_address: T) -> Self {
+        self.to.push((Some(name.as_ref().to_string()), email_address.as_ref().to_string()));
         self
     }
 
     pub fn cc<T: AsRef<str>>(mut self, email_address: T) -> Self {
-        self.cc.push(email_address.as_ref().to_string());
+        self.cc.push((None, email_address.as_ref().to_string()));
+        self
+    }
+
+    /// Same as [`cc`](Self::cc) with a display name, e.g. `Acme Billing <billing@acme.com>`.
+    pub fn cc_named<T: AsRef<str>>(mut self, name: T, email_address: T) -> Self {
+        self.cc.push((Some(name.as_ref().to_string()), email_address.as_ref().to_string()));
+        self
+    }
+
+    /// Adds a blind copy recipient, included in the envelope but never in the message headers.
+    pub fn bcc<T: AsRef<str>>(mut self, email_address: T) -> Self {
+        self.bcc.push(email_address.as_ref().to_string());
+        self
+    }
+
+    /// Sets the address replies should go to, e.g. when sending from a no-reply address.
+    pub fn reply_to<T: AsRef<str>>(mut self, email_address: T) -> Self {
+        self.reply_to.push(email_address.as_ref().to_string());
         self
     }
 
@@ -51,35 +94,91 @@ impl SmtpSender {
         self
     }
 
+    /// Retries transient failures, such as `4xx` responses and connection errors, up to `max_retries` times.
+    /// 
+    /// The delay starts at `backoff`, doubles for every retry and has random jitter added, up to a minute. Permanent
+    /// `5xx` failures are never retried.
+    pub fn retry(mut self, max_retries: u32, backoff: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.retry_backoff = backoff;
+        self
+    }
+
+    /// Sends the message, returning the last error if all attempts fail.
+    /// 
+    /// The error wraps [`lettre::transport::smtp::Error`], which can be used to tell permanent rejections apart,
+    /// e.g. to dead-letter the message.
     pub async fn send(self, message: SmtpMessage) -> anyhow::Result<()> {
         let message = self.build_message(message)?;
         let transport = self.build_transport()?;
-
-        match transport.send(message).await {
-            Ok(_) => Ok(()),
-            Err(err) => Err(anyhow::anyhow!(err.to_string())),
+        let mut attempt = 0;
+
+        loop {
+            let err = match transport.send(message.clone()).await {
+                Ok(_) => return Ok(()),
+                Err(err) => err,
+            };
+
+            if err.is_permanent() || err.is_client() || attempt >= self.max_retries {
+                return Err(err.into());
+            }
+
+            attempt += 1;
+            let delay = utils::retry_delay(self.retry_backoff, attempt, MAX_RETRY_DELAY);
+            tracing::warn!("SMTP send failed, retrying in {:?}: {}", delay, err);
+            tokio::time::sleep(delay).await;
         }
     }
 
-    fn build_message(&self, message: SmtpMessage) -> anyhow::Result<Message> {
+    pub(crate) fn build_message(&self, message: SmtpMessage) -> anyhow::Result<Message> {
         let mut builder = Message::builder();
 
-        builder = match message.content_type {
-            SmtpContentType::TextPlain => builder.header(lettre::message::header::ContentType::TEXT_PLAIN),
-            SmtpContentType::TextHtml => builder.header(lettre::message::header::ContentType::TEXT_HTML),
-        };
-
-        for email in self.from.iter() {
-            builder = builder.from(Mailbox::new(None, email.parse()?));
+        for (name, email) in self.from.iter() {
+            builder = builder.from(Mailbox::new(name.clone(), email.parse()?));
+        }
+        for (name, email) in self.to.iter() {
+            builder = builder.to(Mailbox::new(name.clone(), email.parse()?));
         }
-        for email in self.to.iter() {
-            builder = builder.to(Mailbox::new(None, email.parse()?));
+        for (name, email) in self.cc.iter() {
+            builder = builder.cc(Mailbox::new(name.clone(), email.parse()?));
         }
-        for email in self.cc.iter() {
-            builder = builder.cc(Mailbox::new(None, email.parse()?));
+        for email in self.bcc.iter() {
+            builder = builder.bcc(Mailbox::new(None, email.parse()?));
+        }
+        for email in self.reply_to.iter() {
+            builder = builder.reply_to(Mailbox::new(None, email.parse()?));
         }
         
-        Ok(builder.subject(message.subject).body(message.body)?)
+        let builder = builder.subject(message.subject);
+        let content_type = match message.content_type {
+            SmtpContentType::TextPlain => ContentType::TEXT_PLAIN,
+            SmtpContentType::TextHtml => ContentType::TEXT_HTML,
+        };
+
+        let body = SinglePart::builder().header(content_type).body(message.body);
+        let mut multipart = match message.plain_alternative {
+            Some(plain) => {
+                // Plain text first, clients show the last part they support.
+                let alternative = MultiPart::alternative().singlepart(SinglePart::plain(plain)).singlepart(body);
+                if message.attachments.is_empty() {
+                    return Ok(builder.multipart(alternative)?);
+                }
+                MultiPart::mixed().multipart(alternative)
+            },
+            None => {
+                if message.attachments.is_empty() {
+                    return Ok(builder.singlepart(body)?);
+                }
+                MultiPart::mixed().singlepart(body)
+            },
+        };
+
+        for attachment in message.attachments {
+            let content_type = ContentType::parse(&attachment.content_type)?;
+            multipart = multipart.singlepart(Attachment::new(attachment.file_name).body(attachment.body, content_type));
+        }
+
+        Ok(builder.multipart(multipart)?)
     }
 
     fn build_transport(&self) -> anyhow::Result<AsyncSmtpTransport<Tokio1Executor>> {
@@ -0,0 +1,103 @@
+use std::{sync::{Arc, atomic::{AtomicU32, Ordering}}, time::Duration};
+
+use tokio::{io::AsyncWriteExt, net::TcpListener};
+
+use crate::smtp::{smtp_message::SmtpMessage, smtp_mode::SmtpMode, smtp_sender::SmtpSender};
+
+#[test]
+fn attachment_test() {
+    let sender = SmtpSender::new("127.0.0.1:1025").mode(SmtpMode::Testing).from("from@example.com").to("to@example.com");
+
+    let message = SmtpMessage::new().with_subject("Report").with_body("See attached.");
+    let formatted = String::from_utf8(sender.build_message(message).unwrap().formatted()).unwrap();
+    assert!(!formatted.contains("multipart/mixed"));
+    assert!(formatted.contains("See attached."));
+
+    let message = SmtpMessage::new()
+        .with_subject("Report")
+        .with_body("See attached.")
+        .with_attachment("report.pdf", "application/pdf", b"%PDF".to_vec())
+        .with_attachment("data.csv", "text/csv", "a,b\n1,2");
+    let formatted = String::from_utf8(sender.build_message(message).unwrap().formatted()).unwrap();
+    assert!(formatted.contains("multipart/mixed"));
+    assert!(formatted.contains("See attached."));
+    assert!(formatted.contains("Content-Disposition: attachment; filename=\"report.pdf\""));
+    assert!(formatted.contains("Content-Type: application/pdf"));
+    assert!(formatted.contains("Content-Disposition: attachment; filename=\"data.csv\""));
+    assert!(formatted.contains("Content-Type: text/csv"));
+}
+
+#[test]
+fn alternative_test() {
+    let sender = SmtpSender::new("127.0.0.1:1025").mode(SmtpMode::Testing).from("from@example.com").to("to@example.com");
+
+    let message = SmtpMessage::new().with_subject("Newsletter").with_body_alternative("Plain content", "<p>Html content</p>");
+    let formatted = String::from_utf8(sender.build_message(message).unwrap().formatted()).unwrap();
+    assert!(formatted.contains("multipart/alternative"));
+
+    let plain = formatted.find("Content-Type: text/plain").unwrap();
+    let html = formatted.find("Content-Type: text/html").unwrap();
+    assert!(plain < html);
+    assert!(formatted.contains("Plain content"));
+    assert!(formatted.contains("<p>Html content</p>"));
+}
+
+#[test]
+fn bcc_reply_to_test() {
+    let sender = SmtpSender::new("127.0.0.1:1025").mode(SmtpMode::Testing).from("no-reply@example.com").to("to@example.com").bcc("hidden@example.com").reply_to("support@example.com");
+
+    let message = sender.build_message(SmtpMessage::new().with_subject("Notification").with_body("Hello")).unwrap();
+    assert!(message.envelope().to().iter().any(|address| address.to_string() == "hidden@example.com"));
+
+    let formatted = String::from_utf8(message.formatted()).unwrap();
+    assert!(!formatted.contains("hidden@example.com"));
+    assert!(formatted.contains("Reply-To: support@example.com"));
+}
+
+#[test]
+fn display_name_test() {
+    let sender = SmtpSender::new("127.0.0.1:1025").mode(SmtpMode::Testing).from_named("Acme Billing", "billing@acme.com").to("to@example.com").cc_named("Accounting", "accounting@acme.com");
+
+    let message = sender.build_message(SmtpMessage::new().with_subject("Invoice").with_body("Hello")).unwrap();
+    let formatted = String::from_utf8(message.formatted()).unwrap();
+    assert!(formatted.contains("From: \"Acme Billing\" <billing@acme.com>"));
+    assert!(formatted.contains("Accounting <accounting@acme.com>"));
+    assert!(formatted.contains("To: to@example.com"));
+}
+
+/// Answers every connection with the greeting and counts the connections.
+async fn greeting_server(port: u16, greeting: &'static str) -> Arc<AtomicU32> {
+    let listener = TcpListener::bind(("127.0.0.1", port)).await.unwrap();
+    let connections = Arc::new(AtomicU32::new(0));
+    let counter = connections.clone();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let _ = stream.write_all(greeting.as_bytes()).await;
+        }
+    });
+    connections
+}
+
+#[tokio::test]
+async fn retry_test() {
+    let message = || SmtpMessage::new().with_subject("Retry").with_body("Hello");
+
+    let connections = greeting_server(8121, "421 Service not available\r\n").await;
+    let sender = SmtpSender::new("127.0.0.1:8121").mode(SmtpMode::Testing).from("from@example.com").to("to@example.com").retry(2, Duration::from_millis(10));
+    let err = sender.send(message()).await.unwrap_err();
+    assert!(err.downcast_ref::<lettre::transport::smtp::Error>().unwrap().is_transient());
+    assert_eq!(connections.load(Ordering::SeqCst), 3);
+
+    let connections = greeting_server(8122, "554 No SMTP service here\r\n").await;
+    let sender = SmtpSender::new("127.0.0.1:8122").mode(SmtpMode::Testing).from("from@example.com").to("to@example.com").retry(2, Duration::from_millis(10));
+    let err = sender.send(message()).await.unwrap_err();
+    assert!(err.downcast_ref::<lettre::transport::smtp::Error>().unwrap().is_permanent());
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+
+    let port = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+    let sender = SmtpSender::new(format!("127.0.0.1:{}", port)).mode(SmtpMode::Testing).from("from@example.com").to("to@example.com").retry(2, Duration::from_millis(10));
+    let err = sender.send(message()).await.unwrap_err();
+    let err = err.downcast_ref::<lettre::transport::smtp::Error>().unwrap();
+    assert!(!err.is_permanent() && !err.is_client());
+}